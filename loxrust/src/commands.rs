@@ -0,0 +1,137 @@
+use std::{fs::read_to_string, time::Instant};
+
+use loxrustlib::{
+    err::ScriptError,
+    expr::Expression,
+    interpreter::Interpreter,
+    parser::Parser,
+    scan::Scanner,
+    token::TokenKind,
+};
+
+const HELP: &str = "\
+:env            list the global variables and functions
+:tokens <src>   show the tokens <src> scans into
+:ast <src>      show the statements <src> parses into
+:load <file>    run a script in this session
+:reset          forget everything defined so far, except `args`
+:time <src>     run <src> and show how long it took
+:help           show this list";
+
+/// Runs a line typed into the REPL. A bare expression has its value printed, and lines starting with
+/// `:` are commands to the REPL itself.
+pub fn run_input(interpreter: &mut Interpreter, input: &str) {
+    let result = match input.trim().strip_prefix(':') {
+        Some(command) => run_command(interpreter, command),
+        None => run_source(interpreter, input),
+    };
+
+    if let Err(e) = result {
+        eprint!("{}", e);
+    }
+}
+
+fn run_command(interpreter: &mut Interpreter, command: &str) -> Result<(), ScriptError> {
+    let (name, argument) = command.split_once(char::is_whitespace).unwrap_or((command, ""));
+    let argument = argument.trim();
+
+    match name {
+        "env" => {
+            for (name, value) in interpreter.globals() {
+                println!("var {} = {}", name, value);
+            }
+
+            for name in interpreter.functions() {
+                println!("fun {}", name);
+            }
+        }
+        "tokens" => {
+            for token in Scanner::new(argument) {
+                match token {
+                    Ok(t) if t.kind == TokenKind::Eof => break,
+                    Ok(t) => println!("{}:{} {:?}", t.line, t.column, t.kind),
+                    Err(e) => return Err(ScriptError::Scan(e)),
+                }
+            }
+        }
+        "ast" => match Parser::new(Scanner::new(argument)).parse() {
+            Ok(statements) => statements.iter().for_each(|s| println!("{:#?}", s)),
+            Err(e) => return Err(ScriptError::Parse(e)),
+        },
+        "load" => match read_to_string(argument) {
+            Ok(source) => interpreter.run_source(&source)?,
+            Err(e) => eprintln!("Could not read '{}': {}", argument, e),
+        },
+        "reset" => interpreter.reset(),
+        "time" => {
+            let started = Instant::now();
+            let result = run_source(interpreter, argument);
+
+            println!("Took {:?}", started.elapsed());
+
+            return result;
+        }
+        "help" => println!("{}", HELP),
+        _ => eprintln!("Unknown command ':{}', :help lists the commands", name),
+    }
+
+    Ok(())
+}
+
+/// Runs statements, or evaluates and prints a bare expression like `1 + 2`. `nil` isn't printed, so
+/// calling a function that only has side effects doesn't leave noise behind.
+fn run_source(interpreter: &mut Interpreter, source: &str) -> Result<(), ScriptError> {
+    if Parser::new(Scanner::new(source)).parse_expression().is_err() {
+        return interpreter.run_source(source);
+    }
+
+    match interpreter.eval_expression(source)? {
+        Expression::Nil => Ok(()),
+        value => interpreter.print_value(&value).map_err(ScriptError::Runtime),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use loxrustlib::output::CapturedOutput;
+
+    use super::*;
+
+    fn captured() -> (Interpreter, CapturedOutput) {
+        let output = CapturedOutput::new();
+        let mut interpreter = Interpreter::new();
+        interpreter.set_output(output.clone());
+
+        (interpreter, output)
+    }
+
+    #[test]
+    fn run_source_prints_expression_values_test() {
+        let (mut interpreter, output) = captured();
+
+        assert!(run_source(&mut interpreter, "1 + 2").is_ok());
+        assert!(run_source(&mut interpreter, "\"a\" + \"b\";").is_ok());
+        assert!(run_source(&mut interpreter, "nil").is_ok());
+        assert_eq!("3\nab\n", output.printed());
+    }
+
+    #[test]
+    fn run_source_runs_statements_test() {
+        let (mut interpreter, output) = captured();
+
+        assert!(run_source(&mut interpreter, "var a = 1; a = a + 1;").is_ok());
+        assert!(run_source(&mut interpreter, "fun f() { print a; }").is_ok());
+        assert!(run_source(&mut interpreter, "f()").is_ok());
+        assert!(run_source(&mut interpreter, "a").is_ok());
+        assert_eq!("2\n2\n", output.printed());
+    }
+
+    #[test]
+    fn run_source_reports_errors_test() {
+        let (mut interpreter, output) = captured();
+
+        assert!(matches!(run_source(&mut interpreter, "1 / 0"), Err(ScriptError::Runtime(_))));
+        assert!(matches!(run_source(&mut interpreter, "print ;"), Err(ScriptError::Parse(_))));
+        assert_eq!("", output.printed());
+    }
+}
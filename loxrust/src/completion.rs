@@ -0,0 +1,135 @@
+use std::collections::HashMap;
+
+use loxrustlib::{expr::Expression, interpreter::Interpreter, scan::keywords};
+use rustyline::{
+    completion::Completer, highlight::Highlighter, hint::Hinter, validate::Validator, Context, Helper,
+};
+
+/// Completes keywords and global names, and the members of a global object after `name.`. The editor can't
+/// look into the interpreter while it's reading, so the names are copied over with `update` before each line.
+#[derive(Default)]
+pub struct LoxHelper {
+    names: Vec<String>,
+    members: HashMap<String, Vec<String>>,
+}
+
+impl LoxHelper {
+    pub fn update(&mut self, interpreter: &Interpreter) {
+        let globals = interpreter.globals();
+
+        self.members = globals
+            .iter()
+            .filter_map(|(name, value)| match value {
+                Expression::Object(o) => Some((name.clone(), o.members())),
+                _ => None,
+            })
+            .collect();
+
+        self.names = keywords().map(|k| k.to_string()).collect();
+        self.names.extend(globals.into_iter().map(|(name, _)| name));
+        self.names.extend(interpreter.functions());
+    }
+
+    fn candidates(&self, line: &str) -> (usize, Vec<String>) {
+        let start = word_start(line);
+        let word = &line[start..];
+
+        let options = match line[..start].strip_suffix('.') {
+            Some(before_dot) => self.members.get(&before_dot[word_start(before_dot)..]),
+            None => Some(&self.names),
+        };
+
+        let mut candidates: Vec<_> = options
+            .into_iter()
+            .flatten()
+            .filter(|o| o.starts_with(word))
+            .cloned()
+            .collect();
+
+        candidates.sort();
+        candidates.dedup();
+
+        (start, candidates)
+    }
+}
+
+/// Where the identifier that `line` ends with starts
+fn word_start(line: &str) -> usize {
+    line.char_indices()
+        .rev()
+        .find(|(_, c)| !(c.is_alphanumeric() || *c == '_'))
+        .map_or(0, |(i, c)| i + c.len_utf8())
+}
+
+impl Completer for LoxHelper {
+    type Candidate = String;
+
+    fn complete(&self, line: &str, pos: usize, _: &Context<'_>) -> rustyline::Result<(usize, Vec<String>)> {
+        Ok(self.candidates(&line[..pos]))
+    }
+}
+
+impl Hinter for LoxHelper {
+    type Hint = String;
+}
+
+impl Highlighter for LoxHelper {}
+
+impl Validator for LoxHelper {}
+
+impl Helper for LoxHelper {}
+
+#[cfg(test)]
+mod tests {
+    use loxrustlib::{collections::LoxList, expr::Value};
+
+    use super::*;
+
+    fn helper() -> LoxHelper {
+        let mut interpreter = Interpreter::new();
+        interpreter.set_global("args", LoxList::new(vec![Value::from("x")]).into_value());
+        assert!(interpreter.run_source("var answer = 42; var größe = 1; fun average(a, b) { return (a + b) / 2; }").is_ok());
+
+        // `Completer::update` would be picked over the inherent method for a plain `helper.update`
+        let mut helper = LoxHelper::default();
+        LoxHelper::update(&mut helper, &interpreter);
+
+        helper
+    }
+
+    #[test]
+    fn completes_keywords_and_globals_test() {
+        let helper = helper();
+
+        assert_eq!((6, vec!["while".to_string()]), helper.candidates("print whi"));
+        assert_eq!(vec!["and", "answer", "args", "average"], helper.candidates("a").1);
+        assert_eq!((0, vec!["clock".to_string()]), helper.candidates("cl"));
+    }
+
+    #[test]
+    fn leaves_out_unimplemented_keywords_test() {
+        let helper = helper();
+
+        assert!(helper.candidates("s").1.is_empty());
+        assert!(helper.candidates("th").1.is_empty());
+    }
+
+    #[test]
+    fn completes_object_members_test() {
+        let helper = helper();
+
+        assert_eq!((5, vec!["pop".to_string(), "push".to_string()]), helper.candidates("args.p"));
+        assert_eq!(5, helper.candidates("args.").1.len());
+        assert!(helper.candidates("answer.").1.is_empty());
+    }
+
+    #[test]
+    fn splits_words_on_characters_test() {
+        let helper = helper();
+
+        assert_eq!(6, word_start("print größ"));
+        assert_eq!((8, vec!["größe".to_string()]), helper.candidates("1 + (-2*gr"));
+        assert_eq!(0, word_start("größe"));
+        assert_eq!("«".len(), word_start("«größ"));
+    }
+}
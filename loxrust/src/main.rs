@@ -1,73 +1,208 @@
+mod commands;
+mod completion;
+mod repl;
+
 use std::{
     env,
     fs::read_to_string,
-    io::Write,
+    io::{self, Read},
+    process::ExitCode,
 };
 
-use loxrustlib::{interpreter, parser, scan};
+use loxrustlib::{collections::LoxList, err::ScriptError, expr::Value, interpreter::Interpreter};
 
-fn main() {
-    let args: Vec<String> = env::args().collect();
+const USAGE: &str = "\
+Usage: rlox [--trace] [script | -e code | -] [args...]
 
-    let mut interpreter = interpreter::Interpreter::new();
+  script    run the script in the file
+  -e code   run the code given on the command line
+  -         run the code read from stdin
+  --trace   log every statement and call as it runs
 
-    if args.len() > 2 {
-        print!("Usage: rlox [script]");
-    } else if args.len() == 2 {
-        let scan = match read_to_string(&args[1]) {
-            Ok(a) => a,
-            Err(e) => panic!("{}", e),
-        };
+Without a script, an interactive session is started. The arguments after the script
+are available to it as the list `args`.";
 
-        let scanner = scan::Scanner::new(&scan);
-        let mut parser = parser::Parser::new(scanner);
+// The exit codes from sysexits.h, as the reference implementation uses
+const EX_USAGE: u8 = 64;
+const EX_DATAERR: u8 = 65;
+const EX_NOINPUT: u8 = 66;
+const EX_SOFTWARE: u8 = 70;
+/// What shells report for a process stopped by Ctrl-C
+const EX_INTERRUPTED: u8 = 130;
 
-        let statements = match parser.parse() {
-            Ok(s) => s,
-            Err(e) => {
-                println!("Failed to parse: {}", e);
-                return;
-            }
-        };
+enum Script {
+    Repl,
+    File(String),
+    Inline(String),
+    Stdin,
+}
+
+struct Options {
+    help: bool,
+    trace: bool,
+    script: Script,
+    args: Vec<String>,
+}
+
+fn main() -> ExitCode {
+    let options = match parse_options(env::args().skip(1)) {
+        Ok(options) => options,
+        Err(message) => {
+            eprintln!("{}\n\n{}", message, USAGE);
 
-        match interpreter.interpret(statements) {
-            Ok(_) => (),
-            Err(e) => println!("Failed to execute: {}", e),
+            return ExitCode::from(EX_USAGE);
         }
-    } else {
-        loop {
-            let input = prompt("> ");
+    };
 
-            if input.is_empty() {
-                break;
-            };
+    if options.help {
+        println!("{}", USAGE);
 
-            let scanner = scan::Scanner::new(&input);
-            let mut parser = parser::Parser::new(scanner);
+        return ExitCode::SUCCESS;
+    }
+
+    let mut interpreter = Interpreter::new();
+    interpreter.set_trace(options.trace);
+    interpreter.set_global(
+        "args",
+        LoxList::new(options.args.into_iter().map(Value::from).collect()).into_value(),
+    );
+
+    let interrupt = interpreter.interrupt_handle();
+
+    if let Err(e) = ctrlc::set_handler(move || interrupt.interrupt()) {
+        eprintln!("Ctrl-C won't stop running scripts: {}", e);
+    }
 
-            let statements = match parser.parse() {
-                Ok(s) => s,
+    let source = match options.script {
+        Script::Repl => {
+            return match repl::run(&mut interpreter) {
+                Ok(()) => ExitCode::SUCCESS,
                 Err(e) => {
-                    println!("Failed to parse: {}", e);
-                    continue;
-                }
-            };
+                    eprintln!("{}", e);
 
-            match interpreter.interpret(statements) {
-                Ok(_) => (),
-                Err(e) => println!("Failed to execute: {}", e),
+                    ExitCode::from(EX_SOFTWARE)
+                }
             }
         }
+        Script::File(path) => read_to_string(&path).map_err(|e| format!("Could not read '{}': {}", path, e)),
+        Script::Inline(code) => Ok(code),
+        Script::Stdin => {
+            let mut code = String::new();
+
+            io::stdin()
+                .read_to_string(&mut code)
+                .map(|_| code)
+                .map_err(|e| format!("Could not read stdin: {}", e))
+        }
+    };
+
+    let source = match source {
+        Ok(source) => source,
+        Err(message) => {
+            eprintln!("{}", message);
+
+            return ExitCode::from(EX_NOINPUT);
+        }
+    };
+
+    match interpreter.run_source(&source) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprint!("{}", e);
+
+            ExitCode::from(exit_code(&e))
+        }
     }
 }
 
-fn prompt(name: &str) -> String {
-    let mut line = String::new();
-    print!("{}", name);
-    std::io::stdout().flush().unwrap();
-    std::io::stdin()
-        .read_line(&mut line)
-        .expect("Error: Could not read a line");
+/// Options come first, and everything after the script is passed on to it
+fn parse_options(mut args: impl Iterator<Item = String>) -> Result<Options, String> {
+    let mut help = false;
+    let mut trace = false;
 
-    return line.trim().to_string();
+    let script = loop {
+        let Some(arg) = args.next() else { break Script::Repl };
+
+        match arg.as_str() {
+            "--trace" => trace = true,
+            "-h" | "--help" => help = true,
+            "-e" => match args.next() {
+                Some(code) => break Script::Inline(code),
+                None => return Err("-e needs the code to run".to_string()),
+            },
+            "-" => break Script::Stdin,
+            "--" => match args.next() {
+                Some(path) => break Script::File(path),
+                None => break Script::Repl,
+            },
+            option if option.starts_with('-') => return Err(format!("Unknown option '{}'", option)),
+            _ => break Script::File(arg),
+        }
+    };
+
+    // the REPL is only started once the arguments have run out, so there are none left for it here
+    Ok(Options {
+        help,
+        trace,
+        script,
+        args: args.collect(),
+    })
+}
+
+fn exit_code(error: &ScriptError) -> u8 {
+    match error {
+        ScriptError::Scan(_) | ScriptError::Parse(_) | ScriptError::Resolve(_) => EX_DATAERR,
+        ScriptError::Runtime(_) => EX_SOFTWARE,
+        ScriptError::Interrupted => EX_INTERRUPTED,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &[&str]) -> Result<Options, String> {
+        parse_options(args.iter().map(|a| a.to_string()))
+    }
+
+    #[test]
+    fn parses_scripts_test() {
+        assert!(matches!(parse(&[]).unwrap().script, Script::Repl));
+        assert!(matches!(parse(&["-"]).unwrap().script, Script::Stdin));
+        assert!(matches!(parse(&["-e", "print 1;"]).unwrap().script, Script::Inline(code) if code == "print 1;"));
+        assert!(matches!(parse(&["main.lox"]).unwrap().script, Script::File(path) if path == "main.lox"));
+        assert!(matches!(parse(&["--", "-odd.lox"]).unwrap().script, Script::File(path) if path == "-odd.lox"));
+        assert!(matches!(parse(&["--"]).unwrap().script, Script::Repl));
+    }
+
+    #[test]
+    fn passes_arguments_after_the_script_test() {
+        let options = parse(&["--trace", "main.lox", "a", "--trace", "-e"]).unwrap();
+
+        assert!(options.trace);
+        assert!(!options.help);
+        assert_eq!(vec!["a", "--trace", "-e"], options.args);
+
+        assert_eq!(vec!["x"], parse(&["-e", "print args;", "x"]).unwrap().args);
+        assert_eq!(vec!["x"], parse(&["-", "x"]).unwrap().args);
+        assert!(parse(&["-h"]).unwrap().help);
+    }
+
+    #[test]
+    fn rejects_invalid_options_test() {
+        assert_eq!(Some("Unknown option '--fast'".to_string()), parse(&["--fast", "main.lox"]).err());
+        assert_eq!(Some("-e needs the code to run".to_string()), parse(&["-e"]).err());
+    }
+
+    #[test]
+    fn maps_errors_to_exit_codes_test() {
+        let mut interpreter = Interpreter::new();
+        let mut code = |source: &str| exit_code(&interpreter.run_source(source).unwrap_err());
+
+        assert_eq!(EX_DATAERR, code("print \"unterminated"));
+        assert_eq!(EX_DATAERR, code("print ;"));
+        assert_eq!(EX_DATAERR, code("const a = 1; const a = 2;"));
+        assert_eq!(EX_SOFTWARE, code("print 1 / 0;"));
+        assert_eq!(EX_INTERRUPTED, exit_code(&ScriptError::Interrupted));
+    }
 }
@@ -0,0 +1,128 @@
+use std::{env, path::PathBuf};
+
+use loxrustlib::{
+    err::ErrorKind,
+    interpreter::Interpreter,
+    scan::Scanner,
+    token::{Token, TokenKind},
+};
+use rustyline::{error::ReadlineError, history::FileHistory, Editor};
+
+use crate::{commands, completion::LoxHelper};
+
+const PROMPT: &str = "> ";
+const CONTINUATION_PROMPT: &str = "... ";
+
+/// Reads, runs and repeats until Ctrl-D. Ctrl-C throws away the input typed so far, and lines are kept
+/// in `~/.rlox_history` across sessions. See `commands` for what's done with each input.
+pub fn run(interpreter: &mut Interpreter) -> rustyline::Result<()> {
+    let mut editor: Editor<LoxHelper, FileHistory> = Editor::new()?;
+    editor.set_helper(Some(LoxHelper::default()));
+
+    let history = history_path();
+
+    if let Some(path) = &history {
+        // there's no history yet the first time around
+        let _ = editor.load_history(path);
+    }
+
+    let result = read_loop(&mut editor, interpreter);
+
+    // saved even when reading failed, so the lines typed before it aren't lost
+    match &history {
+        Some(path) => result.and(editor.save_history(path)),
+        None => result,
+    }
+}
+
+fn read_loop(editor: &mut Editor<LoxHelper, FileHistory>, interpreter: &mut Interpreter) -> rustyline::Result<()> {
+    let mut input = String::new();
+
+    loop {
+        let prompt = if input.is_empty() { PROMPT } else { CONTINUATION_PROMPT };
+
+        if let Some(helper) = editor.helper_mut() {
+            helper.update(interpreter);
+        }
+
+        match editor.readline(prompt) {
+            Ok(line) => {
+                input.push_str(&line);
+                input.push('\n');
+
+                if !input.starts_with(':') && is_unfinished(&input) {
+                    continue;
+                }
+
+                editor.add_history_entry(input.trim_end())?;
+
+                // a Ctrl-C pressed while nothing was running shouldn't stop this input
+                interpreter.interrupt_handle().take();
+
+                commands::run_input(interpreter, &input);
+
+                input.clear();
+            }
+            Err(ReadlineError::Interrupted) => input.clear(),
+            Err(ReadlineError::Eof) => return Ok(()),
+            Err(e) => return Err(e),
+        }
+    }
+}
+
+fn history_path() -> Option<PathBuf> {
+    env::var_os("HOME").map(|home| PathBuf::from(home).join(".rlox_history"))
+}
+
+/// Whether the input stops partway through a block, a parenthesized expression, a string or a comment,
+/// meaning there's more to read before running it. Other errors are left for running it to report.
+fn is_unfinished(source: &str) -> bool {
+    let mut depth = 0;
+
+    for token in Scanner::new(source) {
+        match token {
+            Ok(Token { kind: TokenKind::Eof, .. }) => break,
+            Ok(Token {
+                kind: TokenKind::LeftParen | TokenKind::LeftBrace | TokenKind::LeftBracket,
+                ..
+            }) => depth += 1,
+            Ok(Token {
+                kind: TokenKind::RightParen | TokenKind::RightBrace | TokenKind::RightBracket,
+                ..
+            }) => depth -= 1,
+            Ok(_) => (),
+            Err(e) => return e.kind() == ErrorKind::Unterminated,
+        }
+    }
+
+    depth > 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::is_unfinished;
+
+    #[test]
+    fn open_brackets_are_unfinished_test() {
+        assert!(is_unfinished("fun f() {\n"));
+        assert!(is_unfinished("print (1 +\n"));
+        assert!(is_unfinished("if (true) { print [\n"));
+        assert!(!is_unfinished("fun f() { print 1; }\n"));
+    }
+
+    #[test]
+    fn unterminated_strings_and_comments_are_unfinished_test() {
+        assert!(is_unfinished("print \"abc\n"));
+        assert!(is_unfinished("print \"\"\"first line\n"));
+        assert!(is_unfinished("/* a comment\n"));
+        assert!(!is_unfinished("print \"abc\"; /* done */\n"));
+    }
+
+    #[test]
+    fn complete_and_invalid_input_is_run_test() {
+        assert!(!is_unfinished("print 1;\n"));
+        assert!(!is_unfinished("}\n"));
+        assert!(!is_unfinished("print (1));\n"));
+        assert!(!is_unfinished("print @;\n"));
+    }
+}
@@ -0,0 +1,60 @@
+use std::{
+    io::Write,
+    process::{Command, Output, Stdio},
+};
+
+fn rlox(args: &[&str], stdin: &str) -> Output {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_loxrust"))
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+
+    child.stdin.take().unwrap().write_all(stdin.as_bytes()).unwrap();
+
+    child.wait_with_output().unwrap()
+}
+
+fn printed(output: &Output) -> String {
+    String::from_utf8_lossy(&output.stdout).to_string()
+}
+
+#[test]
+pub fn cli_runs_inline_code_with_arguments_test() {
+    let output = rlox(&["-e", "for (a in args) print a;", "first", "--second"], "");
+
+    assert_eq!(Some(0), output.status.code());
+    assert_eq!("first\n--second\n", printed(&output));
+}
+
+#[test]
+pub fn cli_runs_stdin_and_files_test() {
+    let output = rlox(&["-", "x"], "print args;");
+
+    assert_eq!(Some(0), output.status.code());
+    assert_eq!("[x]\n", printed(&output));
+
+    let script = std::env::temp_dir().join(format!("rlox-cli-test-{}.lox", std::process::id()));
+    std::fs::write(&script, "print args.length();").unwrap();
+
+    let output = rlox(&["--", script.to_str().unwrap(), "a", "b"], "");
+    std::fs::remove_file(&script).unwrap();
+
+    assert_eq!(Some(0), output.status.code());
+    assert_eq!("2\n", printed(&output));
+}
+
+#[test]
+pub fn cli_exit_codes_test() {
+    let code = |args: &[&str]| rlox(args, "").status.code();
+
+    assert_eq!(Some(0), code(&["--help"]));
+    assert_eq!(Some(64), code(&["--fast"]));
+    assert_eq!(Some(64), code(&["-e"]));
+    assert_eq!(Some(65), code(&["-e", "print ;"]));
+    assert_eq!(Some(65), code(&["-e", "print \"unterminated"]));
+    assert_eq!(Some(66), code(&["/no/such/script.lox"]));
+    assert_eq!(Some(70), code(&["-e", "print 1 / 0;"]));
+}
@@ -0,0 +1,177 @@
+use std::collections::BTreeMap;
+
+use crate::{
+    err::{ErrorKind, LoxError},
+    expr::{Expression, Value},
+    iterator::{IteratorRef, ValuesIterator},
+    object::{HostObject, ObjectRef},
+};
+
+const LIST_METHODS: &[&str] = &["get", "set", "push", "pop", "length"];
+const MAP_METHODS: &[&str] = &["get", "set", "has", "remove", "keys", "length"];
+
+/// An ordered list of values, for hosts to hand sequences to scripts.
+/// Scripts use `get(index)`, `set(index, value)`, `push(value)`, `pop()` and `length()`, and can iterate over it.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct LoxList {
+    items: Vec<Value>,
+}
+
+impl LoxList {
+    pub fn new(items: Vec<Value>) -> Self {
+        Self { items }
+    }
+
+    pub fn items(&self) -> &[Value] {
+        &self.items
+    }
+
+    pub fn into_value(self) -> Value {
+        Expression::Object(ObjectRef::new(self))
+    }
+
+    fn index(&self, value: &Value) -> Result<usize, LoxError> {
+        let Expression::LiteralInteger(index) = value else {
+            return Err(LoxError::new(ErrorKind::Type, &format!("List index must be an integer, got {}", value)));
+        };
+
+        match usize::try_from(*index) {
+            Ok(i) if i < self.items.len() => Ok(i),
+            _ => Err(LoxError::new(ErrorKind::Index, &format!(
+                "List index {} out of range for length {}",
+                index,
+                self.items.len()
+            ))),
+        }
+    }
+}
+
+impl HostObject for LoxList {
+    fn type_name(&self) -> &str {
+        "List"
+    }
+
+    fn describe(&self) -> String {
+        format!("[{}]", self.items.iter().map(|i| i.to_string()).collect::<Vec<_>>().join(", "))
+    }
+
+    fn describe_cycle(&self) -> String {
+        "[...]".to_string()
+    }
+
+    fn iterate(&self) -> Option<IteratorRef> {
+        Some(IteratorRef::new(ValuesIterator::new(self.items.clone())))
+    }
+
+    fn members(&self) -> Vec<String> {
+        LIST_METHODS.iter().map(|m| m.to_string()).collect()
+    }
+
+    fn call_method(&mut self, name: &str, args: &[Value]) -> Result<Value, LoxError> {
+        match (name, args) {
+            ("get", [index]) => Ok(self.items[self.index(index)?].clone()),
+            ("set", [index, value]) => {
+                let i = self.index(index)?;
+                self.items[i] = value.clone();
+
+                Ok(value.clone())
+            }
+            ("push", [value]) => {
+                self.items.push(value.clone());
+
+                Ok(Expression::Nil)
+            }
+            ("pop", []) => Ok(self.items.pop().unwrap_or(Expression::Nil)),
+            ("length", []) => Ok(Expression::LiteralInteger(self.items.len() as i64)),
+            (name, _) if LIST_METHODS.contains(&name) => Err(wrong_arguments(self.type_name(), name, args)),
+            _ => Err(LoxError::new(ErrorKind::UndefinedProperty, &format!("Undefined method '{}' on List", name))),
+        }
+    }
+}
+
+/// String keys mapped to values, kept sorted by key. Keys can be read and written as properties
+/// (`config.threshold`), and through `get(key)`, `set(key, value)`, `has(key)`, `remove(key)`, `keys()` and
+/// `length()`. Iterating goes through the keys.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct LoxMap {
+    entries: BTreeMap<String, Value>,
+}
+
+impl LoxMap {
+    pub fn new(entries: BTreeMap<String, Value>) -> Self {
+        Self { entries }
+    }
+
+    pub fn entries(&self) -> &BTreeMap<String, Value> {
+        &self.entries
+    }
+
+    pub fn into_value(self) -> Value {
+        Expression::Object(ObjectRef::new(self))
+    }
+
+    fn keys(&self) -> Vec<Value> {
+        self.entries.keys().map(|k| Expression::LiteralString(k.clone())).collect()
+    }
+}
+
+impl HostObject for LoxMap {
+    fn type_name(&self) -> &str {
+        "Map"
+    }
+
+    fn describe(&self) -> String {
+        format!(
+            "{{{}}}",
+            self.entries.iter().map(|(k, v)| format!("{}: {}", k, v)).collect::<Vec<_>>().join(", ")
+        )
+    }
+
+    fn describe_cycle(&self) -> String {
+        "{...}".to_string()
+    }
+
+    fn iterate(&self) -> Option<IteratorRef> {
+        Some(IteratorRef::new(ValuesIterator::new(self.keys())))
+    }
+
+    fn members(&self) -> Vec<String> {
+        self.entries.keys().cloned().chain(MAP_METHODS.iter().map(|m| m.to_string())).collect()
+    }
+
+    fn get(&self, name: &str) -> Option<Value> {
+        self.entries.get(name).cloned()
+    }
+
+    fn set(&mut self, name: &str, value: Value) -> Result<(), LoxError> {
+        self.entries.insert(name.to_string(), value);
+
+        Ok(())
+    }
+
+    fn call_method(&mut self, name: &str, args: &[Value]) -> Result<Value, LoxError> {
+        match (name, args) {
+            ("get", [Expression::LiteralString(key)]) => Ok(self.entries.get(key).cloned().unwrap_or(Expression::Nil)),
+            ("set", [Expression::LiteralString(key), value]) => {
+                self.entries.insert(key.clone(), value.clone());
+
+                Ok(value.clone())
+            }
+            ("has", [Expression::LiteralString(key)]) => Ok(Expression::LiteralBoolean(self.entries.contains_key(key))),
+            ("remove", [Expression::LiteralString(key)]) => Ok(self.entries.remove(key).unwrap_or(Expression::Nil)),
+            ("keys", []) => Ok(LoxList::new(self.keys()).into_value()),
+            ("length", []) => Ok(Expression::LiteralInteger(self.entries.len() as i64)),
+            (name, _) if MAP_METHODS.contains(&name) => Err(wrong_arguments(self.type_name(), name, args)),
+            _ => Err(LoxError::new(ErrorKind::UndefinedProperty, &format!("Undefined method '{}' on Map", name))),
+        }
+    }
+}
+
+fn wrong_arguments(type_name: &str, method: &str, args: &[Value]) -> LoxError {
+    LoxError::new(ErrorKind::Arity, &format!(
+        "Invalid arguments to {}.{}: ({})",
+        type_name,
+        method,
+        args.iter().map(|a| a.to_string()).collect::<Vec<_>>().join(", ")
+    ))
+}
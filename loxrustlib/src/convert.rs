@@ -0,0 +1,728 @@
+use std::{collections::BTreeMap, fmt::Display};
+
+use serde::{
+    de::{self, DeserializeOwned, DeserializeSeed, IntoDeserializer, Visitor},
+    ser::{self, Serialize},
+};
+
+use crate::{
+    collections::{LoxList, LoxMap},
+    err::{ErrorKind, LoxError},
+    expr::{Expression, Value},
+    object::ObjectRef,
+};
+
+/// Converts any serializable Rust value into a Lox value. Structs and maps become `LoxMap`s, sequences and
+/// tuples become `LoxList`s, and enum variants with data become a map from the variant's name to the data.
+pub fn to_value<T: Serialize + ?Sized>(value: &T) -> Result<Value, ConvertError> {
+    value.serialize(ValueSerializer { depth: 0 })
+}
+
+/// Converts a Lox value back into a Rust value, following the same shapes as `to_value`
+pub fn from_value<T: DeserializeOwned>(value: &Value) -> Result<T, ConvertError> {
+    T::deserialize(ValueDeserializer::new(value.clone()))
+}
+
+/// A failed conversion, along with the path to the field it failed at, like `limits[2].name`
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConvertError {
+    path: String,
+    message: String,
+}
+
+impl ConvertError {
+    fn new<T: Display>(message: T) -> Self {
+        Self {
+            path: String::new(),
+            message: message.to_string(),
+        }
+    }
+
+    /// Errors are raised at the innermost value, every container they pass through on the way out
+    /// puts its own part of the path in front
+    fn within(mut self, segment: &str) -> Self {
+        self.path.insert_str(0, segment);
+        self
+    }
+
+    pub fn path(&self) -> &str {
+        self.path.strip_prefix('.').unwrap_or(&self.path)
+    }
+
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+}
+
+impl Display for ConvertError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.path() {
+            "" => write!(f, "{}", self.message),
+            path => write!(f, "{}: {}", path, self.message),
+        }
+    }
+}
+
+impl std::error::Error for ConvertError {}
+
+impl ser::Error for ConvertError {
+    fn custom<T: Display>(msg: T) -> Self {
+        Self::new(msg)
+    }
+}
+
+impl de::Error for ConvertError {
+    fn custom<T: Display>(msg: T) -> Self {
+        Self::new(msg)
+    }
+}
+
+impl From<ConvertError> for LoxError {
+    fn from(err: ConvertError) -> Self {
+        LoxError::new(ErrorKind::Host, &err.to_string())
+    }
+}
+
+fn index_segment(index: usize) -> String {
+    format!("[{}]", index)
+}
+
+fn key_segment(key: &str) -> String {
+    let is_identifier = key.chars().next().is_some_and(|c| c.is_alphabetic() || c == '_')
+        && key.chars().all(|c| c.is_alphanumeric() || c == '_');
+
+    match is_identifier {
+        true => format!(".{}", key),
+        false => format!("[{:?}]", key),
+    }
+}
+
+fn single_entry_map(key: &str, value: Value) -> Value {
+    LoxMap::new(BTreeMap::from([(key.to_string(), value)])).into_value()
+}
+
+/// How deeply `to_value` lets containers nest. Rust values can only get deeper than this by containing
+/// themselves through shared pointers, which would otherwise recurse until the stack overflows.
+const MAX_DEPTH: usize = 128;
+
+#[derive(Clone, Copy)]
+struct ValueSerializer {
+    depth: usize,
+}
+
+impl ValueSerializer {
+    /// The serializer for what's inside of a container
+    fn nested(self) -> Result<Self, ConvertError> {
+        match self.depth < MAX_DEPTH {
+            true => Ok(Self { depth: self.depth + 1 }),
+            false => Err(ConvertError::new(format!(
+                "Nested more than {} levels deep, the value may contain itself",
+                MAX_DEPTH
+            ))),
+        }
+    }
+}
+
+impl ser::Serializer for ValueSerializer {
+    type Ok = Value;
+    type Error = ConvertError;
+
+    type SerializeSeq = SerializeList;
+    type SerializeTuple = SerializeList;
+    type SerializeTupleStruct = SerializeList;
+    type SerializeTupleVariant = SerializeList;
+    type SerializeMap = SerializeMap;
+    type SerializeStruct = SerializeMap;
+    type SerializeStructVariant = SerializeMap;
+
+    fn serialize_bool(self, v: bool) -> Result<Value, ConvertError> {
+        Ok(Expression::LiteralBoolean(v))
+    }
+
+    fn serialize_i8(self, v: i8) -> Result<Value, ConvertError> {
+        self.serialize_i64(v.into())
+    }
+
+    fn serialize_i16(self, v: i16) -> Result<Value, ConvertError> {
+        self.serialize_i64(v.into())
+    }
+
+    fn serialize_i32(self, v: i32) -> Result<Value, ConvertError> {
+        self.serialize_i64(v.into())
+    }
+
+    fn serialize_i64(self, v: i64) -> Result<Value, ConvertError> {
+        Ok(Expression::LiteralInteger(v))
+    }
+
+    fn serialize_u8(self, v: u8) -> Result<Value, ConvertError> {
+        self.serialize_i64(v.into())
+    }
+
+    fn serialize_u16(self, v: u16) -> Result<Value, ConvertError> {
+        self.serialize_i64(v.into())
+    }
+
+    fn serialize_u32(self, v: u32) -> Result<Value, ConvertError> {
+        self.serialize_i64(v.into())
+    }
+
+    fn serialize_u64(self, v: u64) -> Result<Value, ConvertError> {
+        i64::try_from(v)
+            .map(Expression::LiteralInteger)
+            .map_err(|_| ConvertError::new(format!("{} is too large for an integer", v)))
+    }
+
+    fn serialize_f32(self, v: f32) -> Result<Value, ConvertError> {
+        self.serialize_f64(v.into())
+    }
+
+    fn serialize_f64(self, v: f64) -> Result<Value, ConvertError> {
+        Ok(Expression::LiteralNumber(v))
+    }
+
+    fn serialize_char(self, v: char) -> Result<Value, ConvertError> {
+        Ok(Expression::LiteralString(v.to_string()))
+    }
+
+    fn serialize_str(self, v: &str) -> Result<Value, ConvertError> {
+        Ok(Expression::LiteralString(v.to_string()))
+    }
+
+    fn serialize_bytes(self, v: &[u8]) -> Result<Value, ConvertError> {
+        Ok(LoxList::new(v.iter().map(|b| Expression::LiteralInteger((*b).into())).collect()).into_value())
+    }
+
+    fn serialize_none(self) -> Result<Value, ConvertError> {
+        Ok(Expression::Nil)
+    }
+
+    fn serialize_some<T: Serialize + ?Sized>(self, value: &T) -> Result<Value, ConvertError> {
+        value.serialize(self)
+    }
+
+    fn serialize_unit(self) -> Result<Value, ConvertError> {
+        Ok(Expression::Nil)
+    }
+
+    fn serialize_unit_struct(self, _: &'static str) -> Result<Value, ConvertError> {
+        Ok(Expression::Nil)
+    }
+
+    fn serialize_unit_variant(self, _: &'static str, _: u32, variant: &'static str) -> Result<Value, ConvertError> {
+        Ok(Expression::LiteralString(variant.to_string()))
+    }
+
+    fn serialize_newtype_struct<T: Serialize + ?Sized>(self, _: &'static str, value: &T) -> Result<Value, ConvertError> {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T: Serialize + ?Sized>(
+        self,
+        _: &'static str,
+        _: u32,
+        variant: &'static str,
+        value: &T,
+    ) -> Result<Value, ConvertError> {
+        let value = value.serialize(self.nested()?).map_err(|e| e.within(&key_segment(variant)))?;
+
+        Ok(single_entry_map(variant, value))
+    }
+
+    fn serialize_seq(self, len: Option<usize>) -> Result<SerializeList, ConvertError> {
+        Ok(SerializeList {
+            serializer: self.nested()?,
+            variant: None,
+            items: Vec::with_capacity(len.unwrap_or(0)),
+        })
+    }
+
+    fn serialize_tuple(self, len: usize) -> Result<SerializeList, ConvertError> {
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_tuple_struct(self, _: &'static str, len: usize) -> Result<SerializeList, ConvertError> {
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _: &'static str,
+        _: u32,
+        variant: &'static str,
+        len: usize,
+    ) -> Result<SerializeList, ConvertError> {
+        Ok(SerializeList {
+            serializer: self.nested()?,
+            variant: Some(variant),
+            items: Vec::with_capacity(len),
+        })
+    }
+
+    fn serialize_map(self, _: Option<usize>) -> Result<SerializeMap, ConvertError> {
+        Ok(SerializeMap {
+            serializer: self.nested()?,
+            variant: None,
+            entries: BTreeMap::new(),
+            next_key: None,
+        })
+    }
+
+    fn serialize_struct(self, _: &'static str, len: usize) -> Result<SerializeMap, ConvertError> {
+        self.serialize_map(Some(len))
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _: &'static str,
+        _: u32,
+        variant: &'static str,
+        _: usize,
+    ) -> Result<SerializeMap, ConvertError> {
+        Ok(SerializeMap {
+            serializer: self.nested()?,
+            variant: Some(variant),
+            entries: BTreeMap::new(),
+            next_key: None,
+        })
+    }
+}
+
+struct SerializeList {
+    serializer: ValueSerializer,
+    variant: Option<&'static str>,
+    items: Vec<Value>,
+}
+
+impl SerializeList {
+    fn push<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), ConvertError> {
+        let mut path = index_segment(self.items.len());
+
+        if let Some(variant) = self.variant {
+            path.insert_str(0, &key_segment(variant));
+        }
+
+        self.items.push(value.serialize(self.serializer).map_err(|e| e.within(&path))?);
+
+        Ok(())
+    }
+
+    fn finish(self) -> Result<Value, ConvertError> {
+        let list = LoxList::new(self.items).into_value();
+
+        match self.variant {
+            Some(variant) => Ok(single_entry_map(variant, list)),
+            None => Ok(list),
+        }
+    }
+}
+
+impl ser::SerializeSeq for SerializeList {
+    type Ok = Value;
+    type Error = ConvertError;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), ConvertError> {
+        self.push(value)
+    }
+
+    fn end(self) -> Result<Value, ConvertError> {
+        self.finish()
+    }
+}
+
+impl ser::SerializeTuple for SerializeList {
+    type Ok = Value;
+    type Error = ConvertError;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), ConvertError> {
+        self.push(value)
+    }
+
+    fn end(self) -> Result<Value, ConvertError> {
+        self.finish()
+    }
+}
+
+impl ser::SerializeTupleStruct for SerializeList {
+    type Ok = Value;
+    type Error = ConvertError;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), ConvertError> {
+        self.push(value)
+    }
+
+    fn end(self) -> Result<Value, ConvertError> {
+        self.finish()
+    }
+}
+
+impl ser::SerializeTupleVariant for SerializeList {
+    type Ok = Value;
+    type Error = ConvertError;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), ConvertError> {
+        self.push(value)
+    }
+
+    fn end(self) -> Result<Value, ConvertError> {
+        self.finish()
+    }
+}
+
+struct SerializeMap {
+    serializer: ValueSerializer,
+    variant: Option<&'static str>,
+    entries: BTreeMap<String, Value>,
+    next_key: Option<String>,
+}
+
+impl SerializeMap {
+    fn insert<T: Serialize + ?Sized>(&mut self, key: String, value: &T) -> Result<(), ConvertError> {
+        let mut path = key_segment(&key);
+
+        if let Some(variant) = self.variant {
+            path.insert_str(0, &key_segment(variant));
+        }
+
+        let value = value.serialize(self.serializer).map_err(|e| e.within(&path))?;
+        self.entries.insert(key, value);
+
+        Ok(())
+    }
+
+    fn finish(self) -> Result<Value, ConvertError> {
+        let map = LoxMap::new(self.entries).into_value();
+
+        match self.variant {
+            Some(variant) => Ok(single_entry_map(variant, map)),
+            None => Ok(map),
+        }
+    }
+}
+
+impl ser::SerializeMap for SerializeMap {
+    type Ok = Value;
+    type Error = ConvertError;
+
+    fn serialize_key<T: Serialize + ?Sized>(&mut self, key: &T) -> Result<(), ConvertError> {
+        // scripts index maps by strings, so simple keys like numbers are turned into one
+        let key = match key.serialize(self.serializer)? {
+            Expression::LiteralString(s) => s,
+            key @ (Expression::LiteralInteger(_) | Expression::LiteralNumber(_) | Expression::LiteralBoolean(_)) => {
+                key.to_string()
+            }
+            key => return Err(ConvertError::new(format!("Map keys must be strings, got {}", key))),
+        };
+
+        self.next_key = Some(key);
+
+        Ok(())
+    }
+
+    fn serialize_value<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), ConvertError> {
+        let key = self
+            .next_key
+            .take()
+            .ok_or_else(|| ConvertError::new("Map value serialized before its key"))?;
+
+        self.insert(key, value)
+    }
+
+    fn end(self) -> Result<Value, ConvertError> {
+        self.finish()
+    }
+}
+
+impl ser::SerializeStruct for SerializeMap {
+    type Ok = Value;
+    type Error = ConvertError;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, key: &'static str, value: &T) -> Result<(), ConvertError> {
+        self.insert(key.to_string(), value)
+    }
+
+    fn end(self) -> Result<Value, ConvertError> {
+        self.finish()
+    }
+}
+
+impl ser::SerializeStructVariant for SerializeMap {
+    type Ok = Value;
+    type Error = ConvertError;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, key: &'static str, value: &T) -> Result<(), ConvertError> {
+        self.insert(key.to_string(), value)
+    }
+
+    fn end(self) -> Result<Value, ConvertError> {
+        self.finish()
+    }
+}
+
+struct ValueDeserializer {
+    value: Value,
+    /// The lists and maps the value is inside of, so that one containing itself is rejected rather than
+    /// recursed into forever
+    ancestors: Vec<ObjectRef>,
+}
+
+impl ValueDeserializer {
+    fn new(value: Value) -> Self {
+        Self {
+            value,
+            ancestors: Vec::new(),
+        }
+    }
+
+    fn within(value: Value, ancestors: &[ObjectRef]) -> Self {
+        Self {
+            value,
+            ancestors: ancestors.to_vec(),
+        }
+    }
+
+    /// Floats like the result of `10 / 2` are accepted as integers, following `Expression::as_integer`
+    fn integral(&self) -> Option<i64> {
+        match self.value {
+            Expression::LiteralNumber(_) => self.value.as_integer(),
+            _ => None,
+        }
+    }
+}
+
+macro_rules! deserialize_integer {
+    ($($method:ident)*) => {
+        $(
+            fn $method<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, ConvertError> {
+                match self.integral() {
+                    Some(i) => visitor.visit_i64(i),
+                    None => self.deserialize_any(visitor),
+                }
+            }
+        )*
+    };
+}
+
+impl<'de> de::Deserializer<'de> for ValueDeserializer {
+    type Error = ConvertError;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, ConvertError> {
+        match self.value {
+            Expression::Nil => visitor.visit_unit(),
+            Expression::LiteralBoolean(b) => visitor.visit_bool(b),
+            Expression::LiteralInteger(i) => visitor.visit_i64(i),
+            Expression::LiteralNumber(n) => visitor.visit_f64(n),
+            Expression::LiteralString(s) => visitor.visit_string(s),
+            Expression::Object(object) => {
+                if self.ancestors.contains(&object) {
+                    return Err(ConvertError::new(format!("Cannot convert a {} that contains itself", object.type_name())));
+                }
+
+                let mut ancestors = self.ancestors;
+                ancestors.push(object.clone());
+
+                if let Some(list) = object.downcast_ref::<LoxList>() {
+                    let items = list.items().to_vec();
+                    drop(list);
+
+                    return visitor.visit_seq(ListAccess {
+                        items: items.into_iter(),
+                        index: 0,
+                        ancestors,
+                    });
+                }
+
+                if let Some(map) = object.downcast_ref::<LoxMap>() {
+                    let entries = map.entries().clone();
+                    drop(map);
+
+                    return visitor.visit_map(MapAccess {
+                        entries: entries.into_iter(),
+                        next: None,
+                        ancestors,
+                    });
+                }
+
+                Err(ConvertError::new(format!("Cannot convert {} to a Rust value", object.type_name())))
+            }
+            other => Err(ConvertError::new(format!("Cannot convert {} to a Rust value", other))),
+        }
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, ConvertError> {
+        match self.value {
+            Expression::Nil => visitor.visit_none(),
+            _ => visitor.visit_some(self),
+        }
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(self, _: &'static str, visitor: V) -> Result<V::Value, ConvertError> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        _: &'static str,
+        _: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, ConvertError> {
+        if let Expression::LiteralString(variant) = self.value {
+            return visitor.visit_enum(variant.into_deserializer());
+        }
+
+        // a variant with data is a map with a single entry, from the variant's name to the data
+        let entry = match &self.value {
+            Expression::Object(object) => object.downcast_ref::<LoxMap>().and_then(|map| {
+                let mut entries = map.entries().iter();
+
+                match (entries.next(), entries.next()) {
+                    (Some((variant, value)), None) => Some((variant.clone(), value.clone())),
+                    _ => None,
+                }
+            }),
+            _ => None,
+        };
+
+        let Some((variant, value)) = entry else {
+            return Err(ConvertError::new(format!(
+                "Expected an enum variant, either a string or a map with one entry, got {}",
+                self.value
+            )));
+        };
+
+        let mut ancestors = self.ancestors;
+
+        if let Expression::Object(object) = &self.value {
+            ancestors.push(object.clone());
+        }
+
+        visitor.visit_enum(EnumAccess {
+            variant,
+            value,
+            ancestors,
+        })
+    }
+
+    deserialize_integer! {
+        deserialize_i8 deserialize_i16 deserialize_i32 deserialize_i64
+        deserialize_u8 deserialize_u16 deserialize_u32 deserialize_u64
+    }
+
+    serde::forward_to_deserialize_any! {
+        bool i128 u128 f32 f64 char str string bytes byte_buf unit unit_struct seq tuple tuple_struct map struct
+        identifier ignored_any
+    }
+}
+
+struct ListAccess {
+    items: std::vec::IntoIter<Value>,
+    index: usize,
+    ancestors: Vec<ObjectRef>,
+}
+
+impl<'de> de::SeqAccess<'de> for ListAccess {
+    type Error = ConvertError;
+
+    fn next_element_seed<T: DeserializeSeed<'de>>(&mut self, seed: T) -> Result<Option<T::Value>, ConvertError> {
+        let Some(item) = self.items.next() else { return Ok(None) };
+
+        let index = self.index;
+        self.index += 1;
+
+        seed.deserialize(ValueDeserializer::within(item, &self.ancestors))
+            .map(Some)
+            .map_err(|e| e.within(&index_segment(index)))
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.items.len())
+    }
+}
+
+struct MapAccess {
+    entries: std::collections::btree_map::IntoIter<String, Value>,
+    next: Option<(String, Value)>,
+    ancestors: Vec<ObjectRef>,
+}
+
+impl<'de> de::MapAccess<'de> for MapAccess {
+    type Error = ConvertError;
+
+    fn next_key_seed<K: DeserializeSeed<'de>>(&mut self, seed: K) -> Result<Option<K::Value>, ConvertError> {
+        let Some((key, value)) = self.entries.next() else { return Ok(None) };
+
+        let deserialized = seed.deserialize(ValueDeserializer::new(Expression::LiteralString(key.clone())));
+        self.next = Some((key, value));
+
+        deserialized.map(Some)
+    }
+
+    fn next_value_seed<V: DeserializeSeed<'de>>(&mut self, seed: V) -> Result<V::Value, ConvertError> {
+        let (key, value) = self
+            .next
+            .take()
+            .ok_or_else(|| ConvertError::new("Map value read before its key"))?;
+
+        seed.deserialize(ValueDeserializer::within(value, &self.ancestors))
+            .map_err(|e| e.within(&key_segment(&key)))
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.entries.len())
+    }
+}
+
+struct EnumAccess {
+    variant: String,
+    value: Value,
+    ancestors: Vec<ObjectRef>,
+}
+
+impl<'de> de::EnumAccess<'de> for EnumAccess {
+    type Error = ConvertError;
+    type Variant = VariantAccess;
+
+    fn variant_seed<V: DeserializeSeed<'de>>(self, seed: V) -> Result<(V::Value, VariantAccess), ConvertError> {
+        let variant = seed.deserialize(ValueDeserializer::new(Expression::LiteralString(self.variant.clone())))?;
+
+        Ok((
+            variant,
+            VariantAccess {
+                path: key_segment(&self.variant),
+                value: ValueDeserializer::within(self.value, &self.ancestors),
+            },
+        ))
+    }
+}
+
+struct VariantAccess {
+    path: String,
+    value: ValueDeserializer,
+}
+
+impl<'de> de::VariantAccess<'de> for VariantAccess {
+    type Error = ConvertError;
+
+    fn unit_variant(self) -> Result<(), ConvertError> {
+        match self.value.value {
+            Expression::Nil => Ok(()),
+            other => Err(ConvertError::new(format!("Expected no data for a unit variant, got {}", other)).within(&self.path)),
+        }
+    }
+
+    fn newtype_variant_seed<T: DeserializeSeed<'de>>(self, seed: T) -> Result<T::Value, ConvertError> {
+        seed.deserialize(self.value).map_err(|e| e.within(&self.path))
+    }
+
+    fn tuple_variant<V: Visitor<'de>>(self, _: usize, visitor: V) -> Result<V::Value, ConvertError> {
+        de::Deserializer::deserialize_seq(self.value, visitor).map_err(|e| e.within(&self.path))
+    }
+
+    fn struct_variant<V: Visitor<'de>>(
+        self,
+        _: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, ConvertError> {
+        de::Deserializer::deserialize_map(self.value, visitor).map_err(|e| e.within(&self.path))
+    }
+}
@@ -1,11 +1,10 @@
 use std::cell::RefCell;
-use std::{collections::HashMap, rc::Rc};
+use std::{collections::{HashMap, HashSet}, rc::Rc};
 
 use crate::funcs::callable::Callable;
-use crate::{err::LoxError, expr::Expression, token::Token};
+use crate::{err::{ErrorKind, LoxError}, expr::Expression, token::Token};
 use crate::outcome::Outcome;
 use crate::outcome::BreakReason::Errored;
-use crate::outcome::BreakReason::Returned;
 
 #[derive(Default)]
 pub struct Environment {
@@ -13,6 +12,7 @@ pub struct Environment {
 
     callables: HashMap<Identifier, Rc<dyn Callable>>,
     variables: HashMap<Identifier, Option<Expression>>,
+    constants: HashSet<Identifier>,
 }
 
 impl Environment {
@@ -21,6 +21,7 @@ impl Environment {
             parent,
             callables: HashMap::new(),
             variables: HashMap::new(),
+            constants: HashSet::new(),
         }
     }
 
@@ -36,13 +37,20 @@ impl Environment {
     }
 
     pub fn assign(&mut self, name: &Identifier, value: Expression) -> Outcome<()> {
+        if self.constants.contains(name) {
+            return Err(Errored(LoxError::new(ErrorKind::ConstantAssignment, &format!(
+                "Cannot assign to constant '{}'",
+                name.name
+            ))));
+        }
+
         if let std::collections::hash_map::Entry::Occupied(mut e) = self.variables.entry(name.clone()) {
             e.insert(Some(value));
 
             Ok(())
         } else {
             self.parent.as_mut().map_or(
-                Err(Errored(LoxError::with_message(&format!(
+                Err(Errored(LoxError::new(ErrorKind::UndefinedVariable, &format!(
                     "Could not assign nonexistent identifier '{}'",
                     name.name
                 )))),
@@ -51,8 +59,37 @@ impl Environment {
         }
     }
 
-    pub fn define(&mut self, name: Identifier, value: Option<Expression>) {
+    /// Variables can be redeclared in the same scope, constants can't
+    pub fn define(&mut self, name: Identifier, value: Option<Expression>) -> Outcome<()> {
+        self.check_not_constant(&name)?;
         self.variables.insert(name, value);
+
+        Ok(())
+    }
+
+    pub fn define_constant(&mut self, name: Identifier, value: Expression) -> Outcome<()> {
+        self.check_not_constant(&name)?;
+        self.constants.insert(name.clone());
+        self.variables.insert(name, Some(value));
+
+        Ok(())
+    }
+
+    /// Removes a variable or constant from this scope, so the name can be defined again from scratch
+    pub fn undefine(&mut self, name: &Identifier) {
+        self.constants.remove(name);
+        self.variables.remove(name);
+    }
+
+    fn check_not_constant(&self, name: &Identifier) -> Outcome<()> {
+        if self.constants.contains(name) {
+            return Err(Errored(LoxError::new(
+                ErrorKind::ConstantAssignment,
+                &format!("Cannot redeclare constant '{}'", name.name),
+            )));
+        }
+
+        Ok(())
     }
 
     pub fn get(&self, name: &Identifier) -> Option<Expression> {
@@ -69,14 +106,14 @@ impl Environment {
         }
     }
 
-    pub fn print_vars(&self, level: usize) {
-        for (k, v) in &self.variables {
-            println!("{}. {}: {:?}", level, k.name, v);
-        }
+    /// The functions defined in this scope itself, without the ones in its parents
+    pub fn callables(&self) -> impl Iterator<Item = (&Identifier, &Rc<dyn Callable>)> {
+        self.callables.iter()
+    }
 
-        if let Some(upper) = &self.parent {
-            upper.borrow().print_vars(level + 1);
-        }
+    /// The variables defined in this scope itself, without the ones in its parents
+    pub fn variables(&self) -> impl Iterator<Item = (&Identifier, Option<&Expression>)> {
+        self.variables.iter().map(|(k, v)| (k, v.as_ref()))
     }
 }
 
@@ -99,6 +136,14 @@ impl From<&Token> for Identifier {
     }
 }
 
+impl From<&str> for Identifier {
+    fn from(value: &str) -> Self {
+        Self {
+            name: value.to_string(),
+        }
+    }
+}
+
 impl From<&&Token> for Identifier {
     fn from(value: &&Token) -> Self {
         Self {
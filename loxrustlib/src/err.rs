@@ -1,68 +1,302 @@
 use std::{error::Error, fmt, rc::Rc};
 
+use crate::token::Token;
+
+/// What went wrong, for programs that need to tell errors apart without matching on their messages
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ErrorKind {
+    /// Source code that can't be split into tokens, like an unterminated string
+    Lex,
+    /// Source code that ends partway through a string or a block comment, which more input could complete
+    Unterminated,
+    /// Tokens that don't form valid statements
+    Parse,
+    /// Statements that are valid but can't be allowed, when no more specific kind applies
+    Resolve,
+    /// A runtime error that doesn't fit any of the other kinds
+    Runtime,
+    /// An operation applied to values of the wrong type
+    Type,
+    UndefinedVariable,
+    UndefinedFunction,
+    UndefinedProperty,
+    /// A function called with the wrong number of arguments
+    Arity,
+    DivisionByZero,
+    /// Integer arithmetic that doesn't fit in 64 bits
+    Overflow,
+    ConstantAssignment,
+    /// A list index outside of the list
+    Index,
+    StackOverflow,
+    BudgetExceeded,
+    Timeout,
+    /// An error raised by host code, like a host object's method
+    Host,
+    /// Failing to write output
+    Io,
+}
+
+impl ErrorKind {
+    /// A stable identifier for the kind. Codes are never reused or changed once assigned.
+    pub fn code(&self) -> &'static str {
+        match self {
+            ErrorKind::Lex => "E0001",
+            ErrorKind::Parse => "E0002",
+            ErrorKind::Resolve => "E0003",
+            ErrorKind::Unterminated => "E0004",
+            ErrorKind::Runtime => "E0100",
+            ErrorKind::Type => "E0101",
+            ErrorKind::UndefinedVariable => "E0102",
+            ErrorKind::UndefinedFunction => "E0103",
+            ErrorKind::UndefinedProperty => "E0104",
+            ErrorKind::Arity => "E0105",
+            ErrorKind::DivisionByZero => "E0106",
+            ErrorKind::Overflow => "E0107",
+            ErrorKind::ConstantAssignment => "E0108",
+            ErrorKind::Index => "E0109",
+            ErrorKind::StackOverflow => "E0200",
+            ErrorKind::BudgetExceeded => "E0201",
+            ErrorKind::Timeout => "E0202",
+            ErrorKind::Host => "E0300",
+            ErrorKind::Io => "E0301",
+        }
+    }
+}
+
+/// Where in the source an error happened. Lines and columns start at 1.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Span {
+    pub line: usize,
+    /// 0 when only the line is known
+    pub column: usize,
+}
+
+/// A function call that was in progress when an error happened
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Frame {
+    pub function: String,
+    /// The line the function was called from, 0 when it was called by host code
+    pub line: usize,
+}
+
 #[derive(Debug, Clone)]
 pub struct LoxError {
+    kind: ErrorKind,
     line: usize,
+    column: usize,
     message: String,
     internal: Option<Rc<LoxError>>,
+    /// Innermost call first, as the error passes back out through each of them
+    frames: Vec<Frame>,
 }
 
 impl LoxError {
-    pub fn with_message(message: &str) -> Self {
+    pub fn new(kind: ErrorKind, message: &str) -> Self {
         Self {
+            kind,
             message: message.to_string(),
             line: 0,
+            column: 0,
             internal: None,
+            frames: Vec::new(),
         }
     }
 
+    pub fn with_message(message: &str) -> Self {
+        Self::new(ErrorKind::Runtime, message)
+    }
+
     pub fn with_line(message: &str, line: usize) -> Self {
-        Self {
-            message: message.to_string(),
-            line,
-            internal: None,
-        }
+        Self::new(ErrorKind::Runtime, message).or_line(line)
     }
 
     pub fn with_message_line(message: String, line: usize) -> Self {
         Self {
             message,
+            ..Self::with_line("", line)
+        }
+    }
+
+    pub fn kind(&self) -> ErrorKind {
+        self.kind
+    }
+
+    pub fn code(&self) -> &'static str {
+        self.kind.code()
+    }
+
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+
+    /// The calls the error happened in, outermost first
+    pub fn frames(&self) -> impl Iterator<Item = &Frame> {
+        self.frames.iter().rev()
+    }
+
+    /// Records that the error passed out of a call to `function` made at `line`
+    pub fn in_call(mut self, function: &str, line: usize) -> Self {
+        self.frames.push(Frame {
+            function: function.to_string(),
             line,
-            internal: None,
+        });
+
+        self
+    }
+
+    /// The calls the error happened in, printed like Python does: outermost first, each line showing where
+    /// the next call was made and the last one where the error happened. Runs of the same line, as deep
+    /// recursion leaves behind, are collapsed into one. Empty when the error didn't happen inside a call.
+    pub fn traceback(&self) -> String {
+        if self.frames.is_empty() {
+            return String::new();
+        }
+
+        let callers = std::iter::once("<script>").chain(self.frames().map(|f| f.function.as_str()));
+        let lines = self.frames().map(|f| f.line).chain(std::iter::once(self.line));
+        let locations: Vec<_> = lines.zip(callers).collect();
+
+        let mut result = String::from("Traceback (most recent call last):\n");
+        let mut i = 0;
+
+        while i < locations.len() {
+            let run = locations[i..].iter().take_while(|l| **l == locations[i]).count();
+
+            match locations[i] {
+                (0, function) => result.push_str(&format!("  in {}\n", function)),
+                (line, function) => result.push_str(&format!("  line {}, in {}\n", line, function)),
+            }
+
+            if run > 1 {
+                result.push_str(&format!("  [Previous line repeated {} more times]\n", run - 1));
+            }
+
+            i += run;
+        }
+
+        result
+    }
+
+    /// `None` when the error was raised without knowing where in the script it happened
+    pub fn span(&self) -> Option<Span> {
+        match self.line {
+            0 => None,
+            line => Some(Span {
+                line,
+                column: self.column,
+            }),
+        }
+    }
+
+    pub fn of_kind(mut self, kind: ErrorKind) -> Self {
+        self.kind = kind;
+        self
+    }
+
+    /// Sets the kind of errors that don't have a more specific one yet. Each stage of running a script
+    /// tags the errors it raises this way, leaving ones that passed through from an earlier stage alone.
+    pub fn or_kind(self, kind: ErrorKind) -> Self {
+        match self.kind {
+            ErrorKind::Runtime => self.of_kind(kind),
+            _ => self,
+        }
+    }
+
+    /// Fills in the line for errors raised without knowing where in the script they happened
+    pub fn or_line(mut self, line: usize) -> Self {
+        if self.line == 0 {
+            self.line = line;
         }
+
+        self
+    }
+
+    /// Fills in the position of `token` for errors raised without knowing where in the script they happened
+    pub fn or_token(mut self, token: &Token) -> Self {
+        if self.line == 0 {
+            self.line = token.line;
+            self.column = token.column;
+        }
+
+        self
     }
 
+    pub fn or_column(mut self, column: usize) -> Self {
+        if self.column == 0 {
+            self.column = column;
+        }
+
+        self
+    }
+
+    /// An error at `line` that was caused by `err`, which is available through `source`
     pub fn with_internal(err: LoxError, line: usize) -> Self {
-        Self {
-            message: format!("- {}: {}\n", line, err),
-            line,
-            internal: Some(Rc::new(err)),
+        Self::new(err.kind, &err.message.clone()).or_line(line).caused_by(err)
+    }
+
+    /// Records `err` as what led to this error, available through `source`
+    pub fn caused_by(mut self, err: LoxError) -> Self {
+        self.internal = Some(Rc::new(err));
+        self
+    }
+}
+
+/// Everything that can go wrong running a piece of source code, by the stage it went wrong in
+#[derive(Debug, Clone)]
+pub enum ScriptError {
+    Scan(LoxError),
+    Parse(LoxError),
+    Resolve(LoxError),
+    Runtime(LoxError),
+    /// The script was stopped through an `InterruptHandle`
+    Interrupted,
+}
+
+impl ScriptError {
+    /// The underlying error, unless the script was interrupted
+    pub fn lox_error(&self) -> Option<&LoxError> {
+        match self {
+            ScriptError::Scan(e) | ScriptError::Parse(e) | ScriptError::Resolve(e) | ScriptError::Runtime(e) => Some(e),
+            ScriptError::Interrupted => None,
         }
     }
 }
 
+impl fmt::Display for ScriptError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ScriptError::Scan(e) => write!(f, "Failed to scan: {}", e),
+            ScriptError::Parse(e) => write!(f, "Failed to parse: {}", e),
+            ScriptError::Resolve(e) => write!(f, "Failed to resolve: {}", e),
+            ScriptError::Runtime(e) => write!(f, "Failed to execute: {}", e),
+            ScriptError::Interrupted => writeln!(f, "Interrupted"),
+        }
+    }
+}
+
+impl Error for ScriptError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        self.lox_error().map(|e| e as &(dyn Error + 'static))
+    }
+}
+
 impl From<&dyn Error> for LoxError {
     fn from(err: &dyn Error) -> LoxError {
-        LoxError {
-            message: format!("- {}\n", err),
-            line: 0,
-            internal: None,
-        }
+        LoxError::new(ErrorKind::Host, &format!("- {}\n", err))
     }
 }
 
-impl std::error::Error for LoxError {}
+impl std::error::Error for LoxError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        self.internal.as_deref().map(|e| e as &(dyn Error + 'static))
+    }
+}
 
 impl fmt::Display for LoxError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        let Some(internal_error) = &self.internal else {
-            return writeln!(f, "Error occurred at line {}: {}", self.line, self.message)
-        };
-
-        write!(
-            f,
-            "Error occurred at line {}: {}\n{}",
-            self.line, self.message, internal_error
-        )
+        // the internal error is left to `source`, for the caller to walk through
+        writeln!(f, "Error {} occurred at line {}: {}", self.code(), self.line, self.message)?;
+        write!(f, "{}", self.traceback())
     }
 }
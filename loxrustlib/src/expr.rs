@@ -1,6 +1,6 @@
 use std::fmt::Display;
 
-use crate::token::Token;
+use crate::{err::{ErrorKind, LoxError}, iterator::IteratorRef, object::ObjectRef, token::Token};
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum BinaryOperator {
@@ -14,12 +14,21 @@ pub enum BinaryOperator {
     LessThan,
     Multiplication,
     Division,
+    IntegerDivision,
+    Modulo,
+    Power,
+    BitwiseAnd,
+    BitwiseOr,
+    BitwiseXor,
+    ShiftLeft,
+    ShiftRight,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum UnaryOperator {
     Minus,
     Not,
+    BitwiseNot,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -59,21 +68,162 @@ pub enum Expression {
         closing_parenthesis: Token,
         arguments: Vec<Expression>,
     },
+    Interpolation {
+        parts: Vec<Expression>,
+    },
+    Get {
+        object: Box<Expression>,
+        name: Token,
+    },
+    Set {
+        object: Box<Expression>,
+        name: Token,
+        value: Box<Expression>,
+    },
+    Range {
+        start: Box<Expression>,
+        end: Box<Expression>,
+        inclusive: bool,
+    },
+    Iterator(IteratorRef),
+    Object(ObjectRef),
     LiteralNumber(f64),
+    LiteralInteger(i64),
     LiteralBoolean(bool),
     LiteralString(String),
     Nil,
     Identifier(Token)
 }
 
+impl Expression {
+    /// The value as an integer, if it is one or is a float that converts without losing anything. Lox
+    /// arithmetic like `10 / 2` produces floats, so those are accepted wherever an integer is expected.
+    pub fn as_integer(&self) -> Option<i64> {
+        match self {
+            Expression::LiteralInteger(i) => Some(*i),
+            // 2^63 itself is out of range, hence the strict upper bound
+            Expression::LiteralNumber(n) if n.fract() == 0.0 && *n >= i64::MIN as f64 && *n < i64::MAX as f64 => {
+                Some(*n as i64)
+            }
+            _ => None,
+        }
+    }
+}
+
+/// What expressions evaluate to. Runtime values are the literal expressions that would produce them.
+pub type Value = Expression;
+
+impl From<i64> for Expression {
+    fn from(value: i64) -> Self {
+        Expression::LiteralInteger(value)
+    }
+}
+
+impl From<i32> for Expression {
+    fn from(value: i32) -> Self {
+        Expression::LiteralInteger(value.into())
+    }
+}
+
+impl From<f64> for Expression {
+    fn from(value: f64) -> Self {
+        Expression::LiteralNumber(value)
+    }
+}
+
+impl From<bool> for Expression {
+    fn from(value: bool) -> Self {
+        Expression::LiteralBoolean(value)
+    }
+}
+
+impl From<&str> for Expression {
+    fn from(value: &str) -> Self {
+        Expression::LiteralString(value.to_string())
+    }
+}
+
+impl From<String> for Expression {
+    fn from(value: String) -> Self {
+        Expression::LiteralString(value)
+    }
+}
+
+impl From<ObjectRef> for Expression {
+    fn from(value: ObjectRef) -> Self {
+        Expression::Object(value)
+    }
+}
+
+impl<T: Into<Expression>> From<Option<T>> for Expression {
+    fn from(value: Option<T>) -> Self {
+        value.map_or(Expression::Nil, Into::into)
+    }
+}
+
+fn unexpected_type(expected: &str, value: &Expression) -> LoxError {
+    LoxError::new(ErrorKind::Type, &format!("Expected {}, got {}", expected, value))
+}
+
+impl TryFrom<Expression> for i64 {
+    type Error = LoxError;
+
+    /// Accepts the same values as `Expression::as_integer`
+    fn try_from(value: Expression) -> Result<Self, Self::Error> {
+        value.as_integer().ok_or_else(|| unexpected_type("an integer", &value))
+    }
+}
+
+impl TryFrom<Expression> for f64 {
+    type Error = LoxError;
+
+    /// Integers are widened, so a script setting `x = 1` still reads back as a number
+    fn try_from(value: Expression) -> Result<Self, Self::Error> {
+        match value {
+            Expression::LiteralNumber(n) => Ok(n),
+            Expression::LiteralInteger(i) => Ok(i as f64),
+            other => Err(unexpected_type("a number", &other)),
+        }
+    }
+}
+
+impl TryFrom<Expression> for bool {
+    type Error = LoxError;
+
+    fn try_from(value: Expression) -> Result<Self, Self::Error> {
+        match value {
+            Expression::LiteralBoolean(b) => Ok(b),
+            other => Err(unexpected_type("a boolean", &other)),
+        }
+    }
+}
+
+impl TryFrom<Expression> for String {
+    type Error = LoxError;
+
+    fn try_from(value: Expression) -> Result<Self, Self::Error> {
+        match value {
+            Expression::LiteralString(s) => Ok(s),
+            other => Err(unexpected_type("a string", &other)),
+        }
+    }
+}
+
 impl Display for Expression {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
+            // whole floats keep their `.0`, so they can be told apart from integers
+            Expression::LiteralNumber(n) if n.is_finite() && n.fract() == 0.0 => write!(f, "{:.1}", n),
             Expression::LiteralNumber(n) => write!(f, "{}", n),
+            Expression::LiteralInteger(i) => write!(f, "{}", i),
             Expression::LiteralBoolean(b) => write!(f, "{}", b),
             Expression::LiteralString(s) => write!(f, "{}", s),
             Expression::Nil => write!(f, "nil"),
             Expression::Identifier(s) => write!(f, "var {}", s),
+            Expression::Range { start, end, inclusive: false } => write!(f, "{}..{}", start, end),
+            Expression::Range { start, end, inclusive: true } => write!(f, "{}..={}", start, end),
+            Expression::Iterator(_) => write!(f, "<iterator>"),
+            Expression::Object(o) => write!(f, "{}", o.describe()),
             e => write!(f, "{:?}", e),
         }
     }
@@ -92,6 +242,14 @@ impl Display for BinaryOperator {
             BinaryOperator::LessThan => write!(f, "<"),
             BinaryOperator::Multiplication => write!(f, "*"),
             BinaryOperator::Division => write!(f, "/"),
+            BinaryOperator::IntegerDivision => write!(f, "~/"),
+            BinaryOperator::Modulo => write!(f, "%"),
+            BinaryOperator::Power => write!(f, "**"),
+            BinaryOperator::BitwiseAnd => write!(f, "&"),
+            BinaryOperator::BitwiseOr => write!(f, "|"),
+            BinaryOperator::BitwiseXor => write!(f, "^"),
+            BinaryOperator::ShiftLeft => write!(f, "<<"),
+            BinaryOperator::ShiftRight => write!(f, ">>"),
         }
     }
 }
@@ -101,6 +259,7 @@ impl Display for UnaryOperator {
         match self {
             UnaryOperator::Minus => write!(f, "-"),
             UnaryOperator::Not => write!(f, "!"),
+            UnaryOperator::BitwiseNot => write!(f, "~"),
         }
     }
 }
@@ -1,18 +1,20 @@
 use crate::{stmt::Statement, token::Token, interpreter::Interpreter, expr::Expression, outcome::Outcome, environment::Environment};
+use crate::{generator::Generator, iterator::IteratorRef};
 
 use super::callable::Callable;
 
 pub struct LoxDefinedFunction {
     parameters: Vec<Token>,
-    body: Vec<Statement>
+    body: Vec<Statement>,
+    is_generator: bool,
 }
 
 impl LoxDefinedFunction {
-    pub fn new(parameters: Vec<Token>, body: Vec<Statement>) -> Self
+    pub fn new(parameters: Vec<Token>, body: Vec<Statement>, is_generator: bool) -> Self
     where
         Self: Sized {
         Self {
-            parameters, body
+            parameters, body, is_generator
         }
     }
 }
@@ -26,12 +28,16 @@ impl Callable for LoxDefinedFunction {
         let mut env = Environment::new(Some(interpreter.global_env.clone()));
         
         for (i, param_name) in self.parameters.iter().enumerate() {
-            env.define(param_name.clone().into(), args.get(i).cloned());
+            env.define(param_name.clone().into(), args.get(i).cloned())?;
+        }
+
+        // calling a generator doesn't run any of its body, that happens as it's iterated
+        if self.is_generator {
+            return Ok(Expression::Iterator(IteratorRef::new(Generator::new(self.body.clone(), env))));
         }
 
-        
         interpreter.execute_block_statement(&self.body, env)?;
 
         Ok(Expression::Nil)
     }
-}
\ No newline at end of file
+}
@@ -1,3 +1,4 @@
 pub mod loxfunc;
 pub mod callable;
-pub mod clockfunc;
\ No newline at end of file
+pub mod clockfunc;
+pub mod nextfunc;
\ No newline at end of file
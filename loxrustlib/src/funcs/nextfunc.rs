@@ -0,0 +1,40 @@
+use crate::{interpreter::Interpreter, expr::Expression, outcome::Outcome, err::{ErrorKind, LoxError}};
+use crate::outcome::BreakReason::Errored;
+
+use super::callable::Callable;
+
+/// Advances an iterator by hand, returning `nil` once it's exhausted
+pub struct NextFunc {}
+
+impl NextFunc {
+    pub fn new() -> Self
+    where
+        Self: Sized,
+    {
+        Self {}
+    }
+}
+
+impl Default for NextFunc {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Callable for NextFunc {
+    fn arity(&self) -> usize {
+        1
+    }
+
+    fn call(
+        &self,
+        interpreter: &mut Interpreter,
+        args: &[Expression],
+    ) -> Outcome<Expression> {
+        match args.first() {
+            Some(Expression::Iterator(iterator)) => Ok(iterator.next(interpreter, 0)?.unwrap_or(Expression::Nil)),
+            Some(other) => Err(Errored(LoxError::new(ErrorKind::Type, &format!("Cannot call next on {}", other)))),
+            None => Err(Errored(LoxError::new(ErrorKind::Type, "next expects an iterator"))),
+        }
+    }
+}
@@ -0,0 +1,265 @@
+use std::{cell::RefCell, rc::Rc};
+
+use crate::{
+    environment::Environment,
+    expr::Expression,
+    interpreter::Interpreter,
+    iterator::{IteratorRef, LoxIterator},
+    outcome::Outcome,
+    stmt::Statement,
+    token::Token,
+};
+use crate::outcome::BreakReason::Returned;
+
+/// The suspended state of a generator function's body.
+///
+/// The tree-walking interpreter keeps its position on the Rust stack, which can't be paused half way through.
+/// A generator instead keeps its own stack of the loops and blocks it's currently inside of, and only hands
+/// statements that can't yield to the interpreter to run to completion.
+pub struct Generator {
+    frames: Vec<Frame>,
+}
+
+enum Frame {
+    Block {
+        statements: Vec<Statement>,
+        position: usize,
+        env: Rc<RefCell<Environment>>,
+    },
+    While {
+        condition: Expression,
+        body: Statement,
+        line: usize,
+        env: Rc<RefCell<Environment>>,
+    },
+    DoWhile {
+        body: Statement,
+        condition: Expression,
+        started: bool,
+        line: usize,
+        env: Rc<RefCell<Environment>>,
+    },
+    ForIn {
+        variable: Token,
+        iterator: IteratorRef,
+        body: Statement,
+        env: Rc<RefCell<Environment>>,
+    },
+}
+
+impl Generator {
+    pub fn new(body: Vec<Statement>, env: Environment) -> Self {
+        Self {
+            frames: vec![Frame::Block {
+                statements: body,
+                position: 0,
+                env: Rc::new(RefCell::new(env)),
+            }],
+        }
+    }
+
+    /// Runs until the next yield, returning `None` once the body has finished
+    fn resume(&mut self, interpreter: &mut Interpreter) -> Outcome<Option<Expression>> {
+        while let Some(frame) = self.frames.last_mut() {
+            let yielded = match frame {
+                Frame::Block { statements, position, env } => {
+                    let Some(statement) = statements.get(*position).cloned() else {
+                        self.frames.pop();
+                        continue;
+                    };
+
+                    *position += 1;
+
+                    let env = env.clone();
+                    self.step(interpreter, statement, env)?
+                }
+                Frame::While { condition, body, line, env } => {
+                    let (condition, body, line, env) = (condition.clone(), body.clone(), *line, env.clone());
+
+                    let result = interpreter
+                        .with_environment(env.clone(), |i| i.evaluate(&condition))
+                        .map_err(|r| r.or_line(line))?;
+
+                    if !interpreter.is_truthy(&result) {
+                        self.frames.pop();
+                        continue;
+                    }
+
+                    self.step(interpreter, body, env)?
+                }
+                Frame::DoWhile { body, condition, started, line, env } => {
+                    let (condition, body, line, env) = (condition.clone(), body.clone(), *line, env.clone());
+
+                    if *started {
+                        let result = interpreter
+                            .with_environment(env.clone(), |i| i.evaluate(&condition))
+                            .map_err(|r| r.or_line(line))?;
+
+                        if !interpreter.is_truthy(&result) {
+                            self.frames.pop();
+                            continue;
+                        }
+                    }
+
+                    *started = true;
+
+                    self.step(interpreter, body, env)?
+                }
+                Frame::ForIn { variable, iterator, body, env } => {
+                    let (variable, iterator, body, env) = (variable.clone(), iterator.clone(), body.clone(), env.clone());
+
+                    let next = interpreter.with_environment(env.clone(), |i| iterator.next(i, variable.line));
+
+                    let Some(item) = next.map_err(|r| r.or_line(variable.line))? else {
+                        self.frames.pop();
+                        continue;
+                    };
+
+                    let mut iteration_env = Environment::new(Some(env));
+                    iteration_env.define(variable.into(), Some(item))?;
+
+                    self.step(interpreter, body, Rc::new(RefCell::new(iteration_env)))?
+                }
+            };
+
+            if yielded.is_some() {
+                return Ok(yielded);
+            }
+        }
+
+        Ok(None)
+    }
+
+    /// Executes a single statement of the body. Statements that contain a yield push a frame
+    /// instead of running, so that `resume` can pick them up where they left off.
+    fn step(
+        &mut self,
+        interpreter: &mut Interpreter,
+        statement: Statement,
+        env: Rc<RefCell<Environment>>,
+    ) -> Outcome<Option<Expression>> {
+        if !statement.contains_yield() {
+            interpreter.with_environment(env, |i| i.execute(&statement))?;
+
+            return Ok(None);
+        }
+
+        match statement.line() {
+            Some(line) => self.suspend(interpreter, statement, env).map_err(|r| r.or_line(line)),
+            None => self.suspend(interpreter, statement, env),
+        }
+    }
+
+    /// Starts running a statement that contains a yield, pushing a frame for `resume` to continue with
+    fn suspend(
+        &mut self,
+        interpreter: &mut Interpreter,
+        statement: Statement,
+        env: Rc<RefCell<Environment>>,
+    ) -> Outcome<Option<Expression>> {
+        match statement {
+            Statement::YieldStatement { keyword: _, value } => {
+                let value = interpreter.with_environment(env, |i| i.evaluate(&value))?;
+
+                Ok(Some(value))
+            }
+            Statement::BlockStatement { statements } => {
+                self.frames.push(Frame::Block {
+                    statements,
+                    position: 0,
+                    env: Rc::new(RefCell::new(Environment::new(Some(env)))),
+                });
+
+                Ok(None)
+            }
+            Statement::IfStatement {
+                condition,
+                true_branch,
+                else_branch,
+                ..
+            } => {
+                let result = interpreter.with_environment(env.clone(), |i| i.evaluate(&condition))?;
+
+                if interpreter.is_truthy(&result) {
+                    return self.step(interpreter, *true_branch, env);
+                }
+
+                match else_branch {
+                    Some(el) => self.step(interpreter, *el, env),
+                    None => Ok(None),
+                }
+            }
+            Statement::WhileStatement { condition, body, line } => {
+                self.frames.push(Frame::While {
+                    condition,
+                    body: *body,
+                    line,
+                    env,
+                });
+
+                Ok(None)
+            }
+            Statement::DoWhileStatement { body, condition, line } => {
+                self.frames.push(Frame::DoWhile {
+                    body: *body,
+                    condition,
+                    started: false,
+                    line,
+                    env,
+                });
+
+                Ok(None)
+            }
+            Statement::ForInStatement { variable, iterable, body } => {
+                let iterator = interpreter.with_environment(env.clone(), |i| {
+                    let value = i.evaluate(&iterable)?;
+
+                    i.iterate(value)
+                })?;
+
+                self.frames.push(Frame::ForIn {
+                    variable,
+                    iterator,
+                    body: *body,
+                    env,
+                });
+
+                Ok(None)
+            }
+            Statement::MatchStatement { keyword: _, subject, arms } => {
+                let selected = interpreter.with_environment(env.clone(), |i| {
+                    let value = i.evaluate(&subject)?;
+
+                    i.select_match_arm(&value, &arms)
+                        .map(|arm| arm.map(|(arm, arm_env)| (arm.body.clone(), arm_env)))
+                })?;
+
+                match selected {
+                    Some((body, arm_env)) => self.step(interpreter, *body, arm_env),
+                    None => Ok(None),
+                }
+            }
+            // contains_yield only holds for the statements handled above
+            _ => Ok(None),
+        }
+    }
+}
+
+impl LoxIterator for Generator {
+    fn next(&mut self, interpreter: &mut Interpreter) -> Outcome<Option<Expression>> {
+        match self.resume(interpreter) {
+            Ok(Some(value)) => Ok(Some(value)),
+            // a return ends the generator, and so does reaching the end of its body
+            Ok(None) | Err(Returned(_)) => {
+                self.frames.clear();
+
+                Ok(None)
+            }
+            Err(e) => {
+                self.frames.clear();
+
+                Err(e)
+            }
+        }
+    }
+}
@@ -1,24 +1,84 @@
-use std::{rc::Rc, cell::RefCell};
-use crate::{outcome::Outcome, funcs::{clockfunc::ClockFunc, loxfunc::LoxDefinedFunction}};
+use std::{rc::Rc, cell::RefCell, time::Instant};
+use crate::{outcome::Outcome, funcs::{clockfunc::ClockFunc, loxfunc::LoxDefinedFunction, nextfunc::NextFunc}};
+use crate::iterator::{FunctionIterator, IteratorRef, RangeIterator, StringIterator};
+use crate::collections::{LoxList, LoxMap};
+use crate::object::ObjectRef;
 use crate::outcome::BreakReason::Errored;
 use crate::outcome::BreakReason::Returned;
+use crate::output::{Output, StdOutput};
+use crate::funcs::callable::Callable;
+use crate::limits::ExecutionLimits;
+use crate::interrupt::InterruptHandle;
+use crate::outcome::BreakReason::Interrupted;
 
 use crate::{
     environment::{Environment, Identifier},
-    err::LoxError,
-    expr::{BinaryOperator, Expression, LogicalOperator, UnaryOperator},
-    stmt::Statement,
+    err::{ErrorKind, LoxError, ScriptError},
+    parser::Parser,
+    resolver::Resolver,
+    scan::Scanner,
+    expr::{BinaryOperator, Expression, LogicalOperator, UnaryOperator, Value},
+    stmt::{MatchArm, Pattern, Statement},
     token::Token,
 };
 
-#[derive(Default)]
+/// A match arm that was selected, and the scope holding its bindings
+pub type SelectedArm<'a> = (&'a MatchArm, Rc<RefCell<Environment>>);
+
 pub struct Interpreter {
     pub global_env: Rc<RefCell<Environment>>,
-    pub current_env: Rc<RefCell<Environment>>
+    pub current_env: Rc<RefCell<Environment>>,
+    output: Box<dyn Output>,
+    trace: bool,
+    call_depth: usize,
+    limits: ExecutionLimits,
+    steps: u64,
+    started: Instant,
+    interrupt: InterruptHandle,
+    resolver: Resolver,
+    /// What the host passed to `set_global`, to define again after a reset
+    host_globals: Vec<(String, Value)>,
+}
+
+impl Default for Interpreter {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl Interpreter {
     pub fn new() -> Self {
+        let globals = Self::builtins();
+
+        Self {
+            global_env: globals.clone(),
+            current_env: globals,
+            output: Box::new(StdOutput::default()),
+            trace: false,
+            call_depth: 0,
+            limits: ExecutionLimits::default(),
+            steps: 0,
+            started: Instant::now(),
+            interrupt: InterruptHandle::new(),
+            resolver: Resolver::new(),
+            host_globals: Vec::new(),
+        }
+    }
+
+    /// Forgets every variable, constant and function scripts defined, keeping the globals set with
+    /// `set_global`, the output, limits and other settings
+    pub fn reset(&mut self) {
+        self.global_env = Self::builtins();
+        self.current_env = self.global_env.clone();
+        self.resolver = Resolver::new();
+
+        for (name, value) in &self.host_globals {
+            let _ = self.global_env.borrow_mut().define(name.as_str().into(), Some(value.clone()));
+        }
+    }
+
+    /// A global scope holding only the native functions
+    fn builtins() -> Rc<RefCell<Environment>> {
         let globals = Rc::new(RefCell::new(Environment::new(None)));
 
         globals.borrow_mut().define_callable(
@@ -28,38 +88,285 @@ impl Interpreter {
             Rc::new(ClockFunc::new()),
         );
 
+        globals.borrow_mut().define_callable(
+            Identifier {
+                name: "next".to_string(),
+            },
+            Rc::new(NextFunc::new()),
+        );
 
-        Self {
-            global_env: globals.clone(),
-            current_env: globals,
+        globals
+    }
+
+    /// Replaces where `print` statements and diagnostics are written to
+    pub fn set_output<O: Output + 'static>(&mut self, output: O) {
+        self.output = Box::new(output);
+    }
+
+    /// Logs every executed statement, variable definition and assignment, and function call to the
+    /// output's error channel, indented by how deep in calls it happened
+    pub fn set_trace(&mut self, trace: bool) {
+        self.trace = trace;
+    }
+
+    /// The limits apply to each `interpret` call separately
+    pub fn set_limits(&mut self, limits: ExecutionLimits) {
+        self.limits = limits;
+    }
+
+    /// A handle that can stop this interpreter's scripts from another thread
+    pub fn interrupt_handle(&self) -> InterruptHandle {
+        self.interrupt.clone()
+    }
+
+    /// Scans, parses, resolves and runs a piece of source code. Constants are remembered between calls,
+    /// so a REPL can feed it one line at a time.
+    pub fn run_source(&mut self, source: &str) -> Result<(), ScriptError> {
+        let statements = self.parse_source(source)?;
+
+        self.resolver.resolve(&statements).map_err(ScriptError::Resolve)?;
+
+        match self.interpret(statements) {
+            Ok(()) | Err(Returned(_)) => Ok(()),
+            Err(Errored(e)) => Err(ScriptError::Runtime(e)),
+            Err(Interrupted) => Err(ScriptError::Interrupted),
+        }
+    }
+
+    /// Evaluates a single expression, like `1 + 2` or `on_message("hi")`, and returns its value
+    pub fn eval_expression(&mut self, source: &str) -> Result<Value, ScriptError> {
+        let expression = Parser::new(Scanner::new(source))
+            .parse_expression()
+            .map_err(Self::parse_failure)?;
+
+        self.resolver.resolve_expression(&expression).map_err(|e| ScriptError::Resolve(e.or_kind(ErrorKind::Resolve)))?;
+
+        self.start_run();
+
+        match self.evaluate(&expression) {
+            Ok(value) | Err(Returned(value)) => Ok(value),
+            Err(Errored(e)) => Err(ScriptError::Runtime(e)),
+            Err(Interrupted) => Err(ScriptError::Interrupted),
         }
     }
 
+    fn parse_source(&mut self, source: &str) -> Result<Vec<Statement>, ScriptError> {
+        Parser::new(Scanner::new(source)).parse().map_err(Self::parse_failure)
+    }
+
+    /// The parser passes scanning errors on as its own, their kind tells them apart
+    fn parse_failure(e: LoxError) -> ScriptError {
+        match e.kind() {
+            ErrorKind::Lex | ErrorKind::Unterminated => ScriptError::Scan(e),
+            _ => ScriptError::Parse(e),
+        }
+    }
+
+    /// The execution limits apply to each run separately
+    fn start_run(&mut self) {
+        self.steps = 0;
+        self.started = Instant::now();
+    }
+
     pub fn interpret(&mut self, statements: Vec<Statement>) -> Outcome<()> {
+        self.start_run();
+
         for s in statements {
-            if let Err(Errored(e)) = self.execute(&s) {
-                self.dump_environment();
-                println!("{:?}", &s);
+            self.execute(&s)?;
+        }
 
-                return Err(Errored(e));
-            }
+        Ok(())
+    }
+
+    /// Tracing is best effort, there's nowhere left to report a failure to write it
+    fn trace<F: FnOnce() -> String>(&mut self, message: F) {
+        if self.trace {
+            let line = format!("{}{}", "  ".repeat(self.call_depth), message());
+            let _ = self.output.error(&line);
+        }
+    }
+
+    fn count_step(&mut self) -> Outcome<()> {
+        if self.interrupt.take() {
+            return Err(Interrupted);
+        }
+
+        self.steps += 1;
+
+        if self.limits.max_steps.is_some_and(|max| self.steps > max) {
+            return Err(Errored(LoxError::new(ErrorKind::BudgetExceeded, "Execution budget exceeded")));
+        }
+
+        if self.limits.timeout.is_some_and(|timeout| self.started.elapsed() > timeout) {
+            return Err(Errored(LoxError::new(ErrorKind::Timeout, "Execution timed out")));
         }
 
         Ok(())
     }
 
-    fn dump_environment(&mut self) {
-        self.current_env.borrow().print_vars(0);
+    /// Runs `f` one call deeper, failing instead of letting a runaway recursion overflow the native stack
+    pub fn with_call_depth<T, F>(&mut self, line: usize, f: F) -> Outcome<T>
+    where
+        F: FnOnce(&mut Self) -> Outcome<T>,
+    {
+        if self.limits.max_call_depth.is_some_and(|max| self.call_depth >= max) {
+            return Err(Errored(LoxError::new(ErrorKind::StackOverflow, "Stack overflow").or_line(line)));
+        }
+
+        self.call_depth += 1;
+
+        let result = f(self);
+
+        self.call_depth -= 1;
+
+        result
+    }
+
+    /// Calls a function with already evaluated arguments, resolving a `return` into the returned value
+    pub fn call(&mut self, name: &str, callable: Rc<dyn Callable>, args: &[Expression], line: usize) -> Outcome<Expression> {
+        self.trace(|| format!(
+            "call {}({})",
+            name,
+            args.iter().map(|a| a.to_string()).collect::<Vec<_>>().join(", ")
+        ));
+
+        let result = self.with_call_depth(line, |i| match callable.call(i, args) {
+            Ok(e) => Ok(e),
+            Err(Returned(r)) => Ok(r),
+            Err(Errored(e)) => Err(Errored(e.in_call(name, line))),
+            Err(e) => Err(e),
+        });
+
+        self.trace(|| match &result {
+            Ok(value) => format!("{} returned {}", name, value),
+            Err(_) => format!("{} failed", name),
+        });
+
+        result
+    }
+
+    /// Defines a global variable, replacing any variable or constant that already has the name
+    pub fn set_global<V: Into<Value>>(&mut self, name: &str, value: V) {
+        let value = value.into();
+        let mut globals = self.global_env.borrow_mut();
+
+        globals.undefine(&name.into());
+        // nothing is left for the definition to clash with
+        let _ = globals.define(name.into(), Some(value.clone()));
+
+        self.host_globals.retain(|(n, _)| n != name);
+        self.host_globals.push((name.to_string(), value));
+    }
+
+    /// `None` if there's no such variable, or it was declared without a value
+    pub fn get_global(&self, name: &str) -> Option<Value> {
+        self.global_env.borrow().get(&name.into())
+    }
+
+    /// Reads a global variable as a Rust type, failing if it's missing or holds something else
+    pub fn get_global_as<T>(&self, name: &str) -> Result<T, LoxError>
+    where
+        T: TryFrom<Value, Error = LoxError>,
+    {
+        let value = self
+            .get_global(name)
+            .ok_or_else(|| LoxError::new(ErrorKind::UndefinedVariable, &format!("Undefined variable '{}'", name)))?;
+
+        T::try_from(value).map_err(|e| LoxError::new(e.kind(), &format!("Global '{}': {}", name, e.message())).caused_by(e))
+    }
+
+    /// Every global variable, sorted by name. Functions aren't included, and variables declared without a
+    /// value are `nil`.
+    pub fn globals(&self) -> Vec<(String, Value)> {
+        let mut globals: Vec<_> = self
+            .global_env
+            .borrow()
+            .variables()
+            .map(|(name, value)| (name.name.clone(), value.cloned().unwrap_or(Expression::Nil)))
+            .collect();
+
+        globals.sort_by(|(a, _), (b, _)| a.cmp(b));
+
+        globals
+    }
+
+    /// The names of every global function, native ones included, sorted
+    pub fn functions(&self) -> Vec<String> {
+        let mut functions: Vec<_> = self.global_env.borrow().callables().map(|(name, _)| name.name.clone()).collect();
+
+        functions.sort();
+
+        functions
+    }
+
+    /// Calls a function defined by a script, or a native one, from host code. The execution limits apply
+    /// to the call the same way they apply to `interpret`, and an interrupt ends it with
+    /// `ScriptError::Interrupted`.
+    pub fn call_function(&mut self, name: &str, args: &[Value]) -> Result<Value, ScriptError> {
+        let identifier = Identifier {
+            name: name.to_string(),
+        };
+
+        let callable = self
+            .global_env
+            .borrow()
+            .get_callable(&identifier)
+            .ok_or_else(|| {
+                ScriptError::Runtime(LoxError::new(
+                    ErrorKind::UndefinedFunction,
+                    &format!("Call to undefined function '{}'", name),
+                ))
+            })?;
+
+        self.start_run();
+
+        let result = Self::check_arity(name, callable.as_ref(), args.len(), 0)
+            .and_then(|_| self.with_environment(self.global_env.clone(), |i| i.call(name, callable, args, 0)));
+
+        match result {
+            Ok(value) | Err(Returned(value)) => Ok(value),
+            Err(Errored(e)) => Err(ScriptError::Runtime(e)),
+            Err(Interrupted) => Err(ScriptError::Interrupted),
+        }
+    }
+
+    fn check_arity(name: &str, callable: &dyn Callable, count: usize, line: usize) -> Outcome<()> {
+        if count != callable.arity() {
+            return Err(Errored(
+                LoxError::new(
+                    ErrorKind::Arity,
+                    &format!(
+                        "Function '{}' requires {} arguments, but was provided {}.",
+                        name,
+                        callable.arity(),
+                        count
+                    ),
+                )
+                .or_line(line),
+            ));
+        }
+
+        Ok(())
     }
 
     pub fn execute(&mut self, statement: &Statement) -> Outcome<()> {
+        self.count_step()?;
+        self.trace(|| statement.summary());
+
+        match statement.line() {
+            Some(line) => self.execute_statement(statement).map_err(|r| r.or_line(line)),
+            None => self.execute_statement(statement),
+        }
+    }
+
+    fn execute_statement(&mut self, statement: &Statement) -> Outcome<()> {
         match statement {
-            Statement::ExpressionStatement { expression } => {
+            Statement::ExpressionStatement { expression, .. } => {
                 self.evaluate(expression)?;
 
                 Ok(())
             }
-            Statement::PrintStatement { printable } => {
+            Statement::PrintStatement { printable, .. } => {
                 self.print(printable)?;
 
                 Ok(())
@@ -72,6 +379,17 @@ impl Interpreter {
 
                 Ok(())
             }
+            Statement::ConstDeclaration {
+                identifier,
+                initializer,
+            } => {
+                let value = self.evaluate(initializer)?;
+
+                self.trace(|| format!("define {} = {}", identifier.lexeme, value));
+                self.current_env.borrow_mut().define_constant(identifier.into(), value)?;
+
+                Ok(())
+            }
             Statement::BlockStatement { statements } => {
                 self.execute_block_statement(statements, Environment::new(Some(self.current_env.clone())))?;
 
@@ -81,33 +399,58 @@ impl Interpreter {
                 condition,
                 true_branch,
                 else_branch,
+                ..
             } => {
                 self.execute_if(condition, true_branch, else_branch)?;
 
                 Ok(())
             }
-            Statement::WhileStatement { condition, body } => {
+            Statement::WhileStatement { condition, body, .. } => {
                 self.execute_while(condition, body)?;
 
                 Ok(())
             }
-            Statement::FunDeclaration { name, parameters, body } => {
-                self.define_function(name, parameters, body)?;
+            Statement::DoWhileStatement { body, condition, .. } => {
+                self.execute_do_while(body, condition)?;
+
+                Ok(())
+            }
+            Statement::ForInStatement { variable, iterable, body } => {
+                self.execute_for_in(variable, iterable, body)?;
+
+                Ok(())
+            }
+            Statement::FunDeclaration { name, parameters, body, is_generator } => {
+                self.define_function(name, parameters, body, *is_generator)?;
 
                 Ok(())
             },
-            Statement::ReturnStatement { keyword, value } => {
+            Statement::ReturnStatement { keyword: _, value } => {
                 Err(Returned(self.evaluate(value)?))
             },
+            Statement::MatchStatement { keyword: _, subject, arms } => {
+                self.execute_match(subject, arms)?;
+
+                Ok(())
+            }
+            Statement::YieldStatement { keyword, value: _ } => Err(Errored(LoxError::with_line(
+                "Cannot yield outside of a generator function",
+                keyword.line,
+            ))),
         }
     }
 
     fn print(&mut self, expr: &Expression) -> Outcome<()> {
         let result = self.evaluate(expr)?;
 
-        println!("{}", result);
+        self.print_value(&result).map_err(Errored)
+    }
 
-        Ok(())
+    /// Prints a value the way a script's `print` would, to the interpreter's output
+    pub fn print_value(&mut self, value: &Value) -> Result<(), LoxError> {
+        self.output
+            .print(&value.to_string())
+            .map_err(|e| LoxError::new(ErrorKind::Io, &format!("Failed to print: {}", e)))
     }
 
     fn execute_if(
@@ -143,19 +486,160 @@ impl Interpreter {
         Ok(())
     }
 
-    fn define_function(&mut self, name: &Token, parameters: &[Token], body: &[Statement]) -> Outcome<()> {
+    fn execute_do_while(&mut self, body: &Statement, condition: &Expression) -> Outcome<()> {
+        loop {
+            self.execute(body)?;
+
+            let result = self.evaluate(condition)?;
+
+            if !self.is_truthy(&result) {
+                break;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn execute_for_in(&mut self, variable: &Token, iterable: &Expression, body: &Statement) -> Outcome<()> {
+        let value = self.evaluate(iterable)?;
+        let iterator = self.iterate(value)?;
+
+        while let Some(item) = iterator.next(self, variable.line)? {
+            // a fresh scope per iteration holds the loop variable
+            let mut env = Environment::new(Some(self.current_env.clone()));
+            self.trace(|| format!("define {} = {}", variable.lexeme, item));
+            env.define(variable.into(), Some(item))?;
+
+            self.execute_block_statement(std::slice::from_ref(body), env)?;
+        }
+
+        Ok(())
+    }
+
+    /// Turns a value into the iterator `for-in` walks: ranges, the characters of strings, iterators themselves,
+    /// and functions without parameters, which are called until they return `nil`.
+    pub fn iterate(&mut self, value: Expression) -> Outcome<IteratorRef> {
+        match value {
+            Expression::Iterator(iterator) => Ok(iterator),
+            Expression::Range { start, end, inclusive } => match (*start, *end) {
+                (Expression::LiteralInteger(s), Expression::LiteralInteger(e)) => Ok(IteratorRef::new(RangeIterator::new(s, e, inclusive))),
+                (s, e) => Err(Errored(LoxError::with_message(&format!(
+                    "Only ranges between integers can be iterated over, got {}..{}",
+                    s, e
+                )))),
+            },
+            Expression::LiteralString(s) => Ok(IteratorRef::new(StringIterator::new(&s))),
+            Expression::Object(o) => o.iterate().map_err(Errored),
+            Expression::Identifier(t) if self.current_env.borrow().get_callable(&(&t).into()).is_some() => {
+                Ok(IteratorRef::new(FunctionIterator::new(t.into())))
+            }
+            other => Err(Errored(LoxError::new(ErrorKind::Type, &format!("Cannot iterate over {}", other)))),
+        }
+    }
+
+    fn execute_match(&mut self, subject: &Expression, arms: &[MatchArm]) -> Outcome<()> {
+        let value = self.evaluate(subject)?;
+
+        let Some((arm, env)) = self.select_match_arm(&value, arms)? else { return Ok(()); };
+
+        self.with_environment(env, |i| i.execute(&arm.body))
+    }
+
+    /// Finds the first arm matching `value` whose guard passes, along with the scope its body has to run in.
+    /// Every arm gets its own scope, so a binding is only visible to that arm's guard and body.
+    pub fn select_match_arm<'a>(
+        &mut self,
+        value: &Expression,
+        arms: &'a [MatchArm],
+    ) -> Outcome<Option<SelectedArm<'a>>> {
+        for arm in arms {
+            let Some(bindings) = arm.patterns.iter().find_map(|p| {
+                let mut bindings = Vec::new();
+
+                self.matches_pattern(value, p, &mut bindings).then_some(bindings)
+            }) else {
+                continue;
+            };
+
+            let mut env = Environment::new(Some(self.current_env.clone()));
+
+            for (name, value) in bindings {
+                self.trace(|| format!("define {} = {}", name.lexeme, value));
+                env.define(name.into(), Some(value))?;
+            }
+
+            let env = Rc::new(RefCell::new(env));
+
+            let guard_passed = match &arm.guard {
+                Some(guard) => {
+                    let guard_result = self.with_environment(env.clone(), |i| i.evaluate(guard))?;
+
+                    self.is_truthy(&guard_result)
+                }
+                None => true,
+            };
+
+            if guard_passed {
+                return Ok(Some((arm, env)));
+            }
+        }
+
+        Ok(None)
+    }
+
+    /// Whether `value` matches `pattern`, collecting the values its bindings take along the way
+    fn matches_pattern<'p>(&self, value: &Expression, pattern: &'p Pattern, bindings: &mut Vec<(&'p Token, Value)>) -> bool {
+        match pattern {
+            Pattern::Wildcard => true,
+            Pattern::Binding(name) => {
+                bindings.push((name, value.clone()));
+
+                true
+            }
+            Pattern::List(patterns) => {
+                let Expression::Object(object) = value else { return false; };
+                let Some(items) = object.downcast_ref::<LoxList>().map(|l| l.items().to_vec()) else { return false; };
+
+                items.len() == patterns.len() && items.iter().zip(patterns).all(|(item, p)| self.matches_pattern(item, p, bindings))
+            }
+            Pattern::Map(patterns) => {
+                let Expression::Object(object) = value else { return false; };
+                let Some(entries) = object.downcast_ref::<LoxMap>().map(|m| m.entries().clone()) else { return false; };
+
+                patterns.iter().all(|(key, p)| entries.get(key).is_some_and(|v| self.matches_pattern(v, p, bindings)))
+            }
+            Pattern::Literal(literal) => match (value, literal) {
+                (Expression::LiteralInteger(i1), Expression::LiteralInteger(i2)) => i1 == i2,
+                _ => match (self.as_float(value), self.as_float(literal)) {
+                    (Some(n1), Some(n2)) => n1 == n2,
+                    _ => value == literal,
+                },
+            },
+            Pattern::Range { start, end, inclusive } => {
+                if let (Expression::LiteralInteger(i), Expression::LiteralInteger(s), Expression::LiteralInteger(e)) = (value, start, end) {
+                    return s <= i && if *inclusive { i <= e } else { i < e };
+                }
+
+                let (Some(n), Some(s), Some(e)) = (self.as_float(value), self.as_float(start), self.as_float(end)) else { return false; };
+
+                s <= n && if *inclusive { n <= e } else { n < e }
+            }
+        }
+    }
+
+    fn define_function(&mut self, name: &Token, parameters: &[Token], body: &[Statement], is_generator: bool) -> Outcome<()> {
         let identifier = name.into();
 
         if self.current_env.borrow().get_callable(&identifier).is_some() {
             return Err(Errored(LoxError::with_message_line(format!("Function named '{}' already exists", identifier.name), name.line)))
         }
 
-        self.current_env.borrow_mut().define_callable(identifier, Rc::new(LoxDefinedFunction::new(parameters.to_owned(), body.to_owned())));
+        self.current_env.borrow_mut().define_callable(identifier, Rc::new(LoxDefinedFunction::new(parameters.to_owned(), body.to_owned(), is_generator)));
 
         Ok(())
     }
 
-    fn evaluate(&mut self, expression: &Expression) -> Outcome<Expression> {
+    pub fn evaluate(&mut self, expression: &Expression) -> Outcome<Expression> {
         match expression {
             Expression::Assignment {
                 identifier,
@@ -179,20 +663,31 @@ impl Interpreter {
                 closing_parenthesis,
                 arguments,
             } => self.eval_call_expression(callee, closing_parenthesis, arguments), // Avoid clone/copy?
+            Expression::Interpolation { parts } => self.eval_interpolation_expression(parts),
+            Expression::Range { start, end, inclusive } => Ok(Expression::Range {
+                start: Box::new(self.evaluate(start)?),
+                end: Box::new(self.evaluate(end)?),
+                inclusive: *inclusive,
+            }),
+            Expression::Get { object, name } => self.eval_get_expression(object, name),
+            Expression::Set { object, name, value } => self.eval_set_expression(object, name, value),
+            Expression::Iterator(i) => Ok(Expression::Iterator(i.clone())),
+            Expression::Object(o) => Ok(Expression::Object(o.clone())),
             Expression::Identifier(t) => {
                 let env = self.current_env.borrow();
 
-                if let Some(_) = env.get_callable(&t.into()) {
+                if env.get_callable(&t.into()).is_some() {
                     return Ok(Expression::Identifier(t.clone()));
                 }
 
                 let Some(v) = env.get(&t.into()) else { 
-                    return Err(Errored(LoxError::with_message(&format!("Use of undefined variable '{}'", t)))); 
+                    return Err(Errored(LoxError::new(ErrorKind::UndefinedVariable, &format!("Use of undefined variable '{}'", t)).or_token(t))); 
                 };
 
                 Ok(v)
             }
             Expression::LiteralNumber(n) => Ok(Expression::LiteralNumber(*n)), // Avoid clone/copy?
+            Expression::LiteralInteger(i) => Ok(Expression::LiteralInteger(*i)),
             Expression::LiteralBoolean(b) => Ok(Expression::LiteralBoolean(*b)), // Avoid clone/copy?
             Expression::LiteralString(s) => Ok(Expression::LiteralString(s.clone())), // Avoid clone/copy?
             Expression::Nil => Ok(Expression::Nil)
@@ -200,18 +695,24 @@ impl Interpreter {
     }
 
     pub fn execute_block_statement(&mut self, statements: &[Statement], environment: Environment) -> Outcome<()> {
-        let parent_env = std::mem::take(&mut self.current_env);
+        self.with_environment(Rc::new(RefCell::new(environment)), |i| {
+            statements.iter().try_for_each(|stmt| i.execute(stmt))
+        })
+    }
 
-        self.current_env = Rc::new(RefCell::new(Environment::new(Some(parent_env))));
+    /// Runs `f` with `environment` as the current one. The enclosing environment is restored afterwards,
+    /// even when `f` exits early through an error or a return.
+    pub fn with_environment<T, F>(&mut self, environment: Rc<RefCell<Environment>>, f: F) -> Outcome<T>
+    where
+        F: FnOnce(&mut Self) -> Outcome<T>,
+    {
+        let previous_env = std::mem::replace(&mut self.current_env, environment);
 
-        for stmt in statements {
-            self.execute(stmt)?;
-        }
+        let result = f(self);
 
-        let Some(parent) = self.current_env.borrow_mut().parent.take() else { return Err(Errored(LoxError::with_message("No parent environment exists"))); };
-        self.current_env = parent;
+        self.current_env = previous_env;
 
-        Ok(())
+        result
     }
 
     fn declare_variable(
@@ -224,7 +725,11 @@ impl Interpreter {
             .map(|init| self.evaluate(init))
             .transpose()?;
 
-        self.current_env.borrow_mut().define(identifier.into(), init);
+        self.trace(|| match &init {
+            Some(value) => format!("define {} = {}", identifier.lexeme, value),
+            None => format!("define {}", identifier.lexeme),
+        });
+        self.current_env.borrow_mut().define(identifier.into(), init)?;
 
         Ok(())
     }
@@ -236,6 +741,7 @@ impl Interpreter {
     ) -> Outcome<Expression> {
         let value = self.evaluate(expression)?;
 
+        self.trace(|| format!("assign {} = {}", identifier.lexeme, value));
         self.current_env.borrow_mut().assign(&identifier.into(), value.clone())?;
 
         Ok(value)
@@ -256,22 +762,42 @@ impl Interpreter {
         result
     }
 
+    fn eval_interpolation_expression(&mut self, parts: &[Expression]) -> Outcome<Expression> {
+        let mut result = String::new();
+
+        for part in parts {
+            // stringify each part the same way print does
+            result.push_str(&self.evaluate(part)?.to_string());
+        }
+
+        Ok(Expression::LiteralString(result))
+    }
+
     fn eval_unary_expression(
         &mut self,
         operator: UnaryOperator,
         right: &Expression,
     ) -> Outcome<Expression> {
         match operator {
-            UnaryOperator::Minus => {
-                let Expression::LiteralNumber(right_num) = self.evaluate(right)? else { return Err(Errored(LoxError::with_message("Only a number can be negated this way"))); };
-
-                Ok(Expression::LiteralNumber(-right_num))
-            }
+            UnaryOperator::Minus => match self.evaluate(right)? {
+                Expression::LiteralNumber(right_num) => Ok(Expression::LiteralNumber(-right_num)),
+                Expression::LiteralInteger(right_int) => right_int
+                    .checked_neg()
+                    .map(Expression::LiteralInteger)
+                    .ok_or_else(|| Errored(LoxError::new(ErrorKind::Overflow, &format!("Integer overflow negating {}", right_int)))),
+                _ => Err(Errored(LoxError::new(ErrorKind::Type, "Only a number can be negated this way"))),
+            },
             UnaryOperator::Not => {
                 let left_result = self.evaluate(right)?;
 
                 Ok(Expression::LiteralBoolean(!self.is_truthy(&left_result)))
             }
+            UnaryOperator::BitwiseNot => {
+                let right_result = self.evaluate(right)?;
+                let right_int = self.as_integral(&right_result, "Only an integral number can be bitwise negated")?;
+
+                Ok(Expression::LiteralInteger(!right_int))
+            }
         }
     }
 
@@ -294,12 +820,64 @@ impl Interpreter {
         self.evaluate(right)
     }
 
+    fn eval_object(&mut self, object: &Expression, name: &Token) -> Outcome<ObjectRef> {
+        match self.evaluate(object)? {
+            Expression::Object(o) => Ok(o),
+            other => Err(Errored(
+                LoxError::new(
+                    ErrorKind::Type,
+                    &format!("Cannot access '{}' on {}, only objects have properties", name.lexeme, other),
+                )
+                .or_token(name),
+            )),
+        }
+    }
+
+    fn eval_get_expression(&mut self, object: &Expression, name: &Token) -> Outcome<Expression> {
+        let object = self.eval_object(object, name)?;
+
+        match object.get(&name.lexeme).map_err(|e| Errored(e.or_token(name)))? {
+            Some(value) => Ok(value),
+            None => Err(Errored(
+                LoxError::new(
+                    ErrorKind::UndefinedProperty,
+                    &format!("Undefined property '{}' on {}", name.lexeme, object.type_name()),
+                )
+                .or_token(name),
+            )),
+        }
+    }
+
+    fn eval_set_expression(&mut self, object: &Expression, name: &Token, value: &Expression) -> Outcome<Expression> {
+        let object = self.eval_object(object, name)?;
+        let value = self.evaluate(value)?;
+
+        object
+            .set(&name.lexeme, value.clone())
+            .map_err(|e| Errored(e.or_token(name)))?;
+
+        Ok(value)
+    }
+
+    fn eval_method_call(&mut self, object: &Expression, name: &Token, arguments: &[Expression]) -> Outcome<Expression> {
+        let object = self.eval_object(object, name)?;
+        let args = arguments.iter().map(|a| self.evaluate(a)).collect::<Result<Vec<_>, _>>()?;
+
+        object
+            .call_method(&name.lexeme, &args)
+            .map_err(|e| Errored(e.or_token(name)))
+    }
+
     fn eval_call_expression(
         &mut self,
         callee: &Expression,
         closing_parenthesis: &Token,
-        arguments: &Vec<Expression>,
+        arguments: &[Expression],
     ) -> Outcome<Expression> {
+        if let Expression::Get { object, name } = callee {
+            return self.eval_method_call(object, name, arguments);
+        }
+
         let identifier;
 
         if let Expression::Identifier(t) = callee {
@@ -315,31 +893,20 @@ impl Interpreter {
         }
 
         let Some(callable) = self.current_env.borrow().get_callable(&identifier) else {
-            return Err(Errored(LoxError::with_message_line(
-                format!("Call to undefined function '{}'", identifier.name),
-                closing_parenthesis.line,
-            )));
+            return Err(Errored(
+                LoxError::new(
+                    ErrorKind::UndefinedFunction,
+                    &format!("Call to undefined function '{}'", identifier.name),
+                )
+                .or_line(closing_parenthesis.line),
+            ));
         };
 
-        if arguments.len() != callable.arity() {
-            return Err(Errored(LoxError::with_message_line(
-                format!(
-                    "Function '{}' requires {} arguments, but was provided {}.",
-                    identifier.name,
-                    callable.arity(),
-                    arguments.len()
-                ),
-                closing_parenthesis.line,
-            )));
-        }
+        Self::check_arity(&identifier.name, callable.as_ref(), arguments.len(), closing_parenthesis.line)?;
 
         let x = arguments.iter().map(|a| self.evaluate(a)).collect::<Result<Vec<_>, _>>()?;
 
-        match callable.call(self, &x) {
-            Ok(e) => Ok(e),
-            Err(Returned(r)) => Ok(r),
-            Err(Errored(e)) => Err(Errored(e))
-        }
+        self.call(&identifier.name, callable, &x, closing_parenthesis.line)
     }
 
     fn eval_binary_expression(
@@ -356,6 +923,7 @@ impl Interpreter {
                 l,
                 r,
                 "Subtraction requires both operands to be numbers",
+                |i1, i2| Ok(i1.checked_sub(i2)),
                 |n1, n2| Ok(n1 - n2),
             ),
             BinaryOperator::Plus => {
@@ -371,12 +939,14 @@ impl Interpreter {
                     l,
                     r,
                     "Addition requires both operands to be numbers",
+                    |i1, i2| Ok(i1.checked_add(i2)),
                     |n1, n2| Ok(n1 + n2),
                 )
             }
             BinaryOperator::NotEqual => self.comparison(
                 l,
                 r,
+                |i1, i2| i1 != i2,
                 |n1, n2| n1 != n2,
                 |s1, s2| s1 != s2,
                 |b1, b2| b1 != b2,
@@ -384,6 +954,7 @@ impl Interpreter {
             BinaryOperator::GreaterThanOrEqual => self.comparison(
                 l,
                 r,
+                |i1, i2| i1 >= i2,
                 |n1, n2| n1 >= n2,
                 |s1, s2| s1 >= s2,
                 |b1, b2| b1 >= b2,
@@ -391,6 +962,7 @@ impl Interpreter {
             BinaryOperator::LessThanOrEqual => self.comparison(
                 l,
                 r,
+                |i1, i2| i1 <= i2,
                 |n1, n2| n1 <= n2,
                 |s1, s2| s1 <= s2,
                 |b1, b2| b1 <= b2,
@@ -398,38 +970,165 @@ impl Interpreter {
             BinaryOperator::Equal => self.comparison(
                 l,
                 r,
+                |i1, i2| i1 == i2,
                 |n1, n2| n1 == n2,
                 |s1, s2| s1 == s2,
                 |b1, b2| b1 == b2,
             ),
-            BinaryOperator::GreaterThan => {
-                self.comparison(l, r, |n1, n2| n1 > n2, |s1, s2| s1 > s2, |b1, b2| b1 & !b2)
-            }
-            BinaryOperator::LessThan => {
-                self.comparison(l, r, |n1, n2| n1 < n2, |s1, s2| s1 < s2, |b1, b2| !b1 & b2)
-            }
+            BinaryOperator::GreaterThan => self.comparison(
+                l,
+                r,
+                |i1, i2| i1 > i2,
+                |n1, n2| n1 > n2,
+                |s1, s2| s1 > s2,
+                |b1, b2| b1 & !b2,
+            ),
+            BinaryOperator::LessThan => self.comparison(
+                l,
+                r,
+                |i1, i2| i1 < i2,
+                |n1, n2| n1 < n2,
+                |s1, s2| s1 < s2,
+                |b1, b2| !b1 & b2,
+            ),
             BinaryOperator::Multiplication => self.numeric_operation(
                 l,
                 r,
                 "Multiplication requires both operands to be numbers",
+                |i1, i2| Ok(i1.checked_mul(i2)),
                 |n1, n2| Ok(n1 * n2),
             ),
-            BinaryOperator::Division => self.numeric_operation(
+            // true division always produces a float, `~/` is the integer-preserving variant
+            BinaryOperator::Division => self.float_operation(
                 l,
                 r,
                 "Division requires both operands to be numbers",
                 |n1, n2| {
                     if n2 == 0.0 {
-                        return Err(Errored(LoxError::with_message("Division by 0")));
+                        return Err(Errored(LoxError::new(ErrorKind::DivisionByZero, "Division by 0")));
                     }
 
                     Ok(n1 / n2)
                 },
             ),
+            BinaryOperator::IntegerDivision => self.numeric_operation(
+                l,
+                r,
+                "Integer division requires both operands to be numbers",
+                |i1, i2| {
+                    if i2 == 0 {
+                        return Err(Errored(LoxError::new(ErrorKind::DivisionByZero, "Division by 0")));
+                    }
+
+                    // round towards negative infinity, like the float variant
+                    Ok(i1.checked_div(i2).map(|q| if i1 % i2 != 0 && (i1 < 0) != (i2 < 0) { q - 1 } else { q }))
+                },
+                |n1, n2| {
+                    if n2 == 0.0 {
+                        return Err(Errored(LoxError::new(ErrorKind::DivisionByZero, "Division by 0")));
+                    }
+
+                    Ok((n1 / n2).floor())
+                },
+            ),
+            BinaryOperator::Modulo => self.numeric_operation(
+                l,
+                r,
+                "Modulo requires both operands to be numbers",
+                |i1, i2| {
+                    if i2 == 0 {
+                        return Err(Errored(LoxError::new(ErrorKind::DivisionByZero, "Modulo by 0")));
+                    }
+
+                    // the remainder always fits, even where the quotient doesn't, like i64::MIN % -1
+                    let m = i1.wrapping_rem(i2);
+
+                    Ok(Some(if m != 0 && (m < 0) != (i2 < 0) { m + i2 } else { m }))
+                },
+                |n1, n2| {
+                    if n2 == 0.0 {
+                        return Err(Errored(LoxError::new(ErrorKind::DivisionByZero, "Modulo by 0")));
+                    }
+
+                    // floored modulo, so that `a == b * (a ~/ b) + a % b` always holds
+                    Ok(n1 - n2 * (n1 / n2).floor())
+                },
+            ),
+            BinaryOperator::Power => match (l, r) {
+                // a negative exponent can't produce an integer, so let it fall through to floats
+                (Expression::LiteralInteger(_), Expression::LiteralInteger(exponent)) if *exponent >= 0 => self.numeric_operation(
+                    l,
+                    r,
+                    "Exponentiation requires both operands to be numbers",
+                    |i1, i2| Ok(u32::try_from(i2).ok().and_then(|e| i1.checked_pow(e))),
+                    |n1, n2| Ok(n1.powf(n2)),
+                ),
+                _ => self.float_operation(
+                    l,
+                    r,
+                    "Exponentiation requires both operands to be numbers",
+                    |n1, n2| Ok(n1.powf(n2)),
+                ),
+            },
+            BinaryOperator::BitwiseAnd => self.integral_operation(
+                l,
+                r,
+                "Bitwise and requires both operands to be integral numbers",
+                |i1, i2| Ok(i1 & i2),
+            ),
+            BinaryOperator::BitwiseOr => self.integral_operation(
+                l,
+                r,
+                "Bitwise or requires both operands to be integral numbers",
+                |i1, i2| Ok(i1 | i2),
+            ),
+            BinaryOperator::BitwiseXor => self.integral_operation(
+                l,
+                r,
+                "Bitwise xor requires both operands to be integral numbers",
+                |i1, i2| Ok(i1 ^ i2),
+            ),
+            BinaryOperator::ShiftLeft => self.integral_operation(
+                l,
+                r,
+                "Shifting requires both operands to be integral numbers",
+                |i1, i2| Ok(i1 << self.shift_amount(i2)?),
+            ),
+            BinaryOperator::ShiftRight => self.integral_operation(
+                l,
+                r,
+                "Shifting requires both operands to be integral numbers",
+                |i1, i2| Ok(i1 >> self.shift_amount(i2)?),
+            ),
+        }
+    }
+
+    /// Applies `int_op` when both operands are integers, and `op` otherwise, promoting any integer operand
+    /// to a float. `int_op` returns `None` when the result overflows.
+    fn numeric_operation<I, N>(
+        &self,
+        left: &Expression,
+        right: &Expression,
+        invalid_operands_message: &str,
+        int_op: I,
+        op: N,
+    ) -> Outcome<Expression>
+    where
+        I: Fn(i64, i64) -> Outcome<Option<i64>>,
+        N: Fn(f64, f64) -> Outcome<f64>,
+    {
+        if let (Expression::LiteralInteger(left_int), Expression::LiteralInteger(right_int)) = (left, right) {
+            let Some(result) = int_op(*left_int, *right_int)? else {
+                return Err(Errored(LoxError::new(ErrorKind::Overflow, &format!("Integer overflow evaluating {} and {}", left_int, right_int))));
+            };
+
+            return Ok(Expression::LiteralInteger(result));
         }
+
+        self.float_operation(left, right, invalid_operands_message, op)
     }
 
-    fn numeric_operation<N>(
+    fn float_operation<N>(
         &self,
         left: &Expression,
         right: &Expression,
@@ -439,41 +1138,88 @@ impl Interpreter {
     where
         N: Fn(f64, f64) -> Outcome<f64>,
     {
-        let Expression::LiteralNumber(left_num) = left else { return Err(Errored(LoxError::with_message(invalid_operands_message))); };
-        let Expression::LiteralNumber(right_num) = right else { return Err(Errored(LoxError::with_message(invalid_operands_message))); };
+        let Some(left_num) = self.as_float(left) else { return Err(Errored(LoxError::new(ErrorKind::Type, invalid_operands_message))); };
+        let Some(right_num) = self.as_float(right) else { return Err(Errored(LoxError::new(ErrorKind::Type, invalid_operands_message))); };
+
+        Ok(Expression::LiteralNumber(op(left_num, right_num)?))
+    }
+
+    fn as_float(&self, expr: &Expression) -> Option<f64> {
+        match expr {
+            Expression::LiteralNumber(n) => Some(*n),
+            Expression::LiteralInteger(i) => Some(*i as f64),
+            _ => None,
+        }
+    }
+
+    fn integral_operation<N>(
+        &self,
+        left: &Expression,
+        right: &Expression,
+        invalid_operands_message: &str,
+        op: N,
+    ) -> Outcome<Expression>
+    where
+        N: Fn(i64, i64) -> Outcome<i64>,
+    {
+        let left_int = self.as_integral(left, invalid_operands_message)?;
+        let right_int = self.as_integral(right, invalid_operands_message)?;
+
+        Ok(Expression::LiteralInteger(op(left_int, right_int)?))
+    }
+
+    fn as_integral(&self, expr: &Expression, invalid_operand_message: &str) -> Outcome<i64> {
+        expr.as_integer()
+            .ok_or_else(|| Errored(LoxError::new(ErrorKind::Type, invalid_operand_message)))
+    }
+
+    fn shift_amount(&self, amount: i64) -> Outcome<u32> {
+        if !(0..64).contains(&amount) {
+            return Err(Errored(LoxError::new(ErrorKind::Overflow, &format!(
+                "Cannot shift by {}, the shift amount must be between 0 and 63",
+                amount
+            ))));
+        }
 
-        Ok(Expression::LiteralNumber(op(*left_num, *right_num)?))
+        Ok(amount as u32)
     }
 
-    fn comparison<N, S, B>(
+    fn comparison<I, N, S, B>(
         &self,
         left: &Expression,
         right: &Expression,
+        i: I,
         n: N,
         s: S,
         b: B,
     ) -> Outcome<Expression>
     where
+        I: Fn(i64, i64) -> bool,
         N: Fn(f64, f64) -> bool,
         S: Fn(&str, &str) -> bool,
         B: Fn(bool, bool) -> bool,
     {
         match left {
-            Expression::LiteralNumber(left_num) => {
+            Expression::LiteralInteger(_) | Expression::LiteralNumber(_) => {
                 if right == &Expression::Nil {
                     return Ok(Expression::LiteralBoolean(false));
                 }
 
-                let Expression::LiteralNumber(right_num) = right else { return Err(Errored(LoxError::with_message("Cannot compare unlike types"))); };
+                // integers are compared exactly, anything mixed is compared as floats
+                if let (Expression::LiteralInteger(left_int), Expression::LiteralInteger(right_int)) = (left, right) {
+                    return Ok(Expression::LiteralBoolean(i(*left_int, *right_int)));
+                }
+
+                let (Some(left_num), Some(right_num)) = (self.as_float(left), self.as_float(right)) else { return Err(Errored(LoxError::new(ErrorKind::Type, "Cannot compare unlike types"))); };
 
-                Ok(Expression::LiteralBoolean(n(*left_num, *right_num)))
+                Ok(Expression::LiteralBoolean(n(left_num, right_num)))
             }
             Expression::LiteralString(left_string) => {
                 if right == &Expression::Nil {
                     return Ok(Expression::LiteralBoolean(false));
                 }
 
-                let Expression::LiteralString(right_string) = right else { return Err(Errored(LoxError::with_message("Cannot compare unlike types"))); };
+                let Expression::LiteralString(right_string) = right else { return Err(Errored(LoxError::new(ErrorKind::Type, "Cannot compare unlike types"))); };
 
                 Ok(Expression::LiteralBoolean(s(left_string, right_string)))
             }
@@ -482,10 +1228,11 @@ impl Interpreter {
                     return Ok(Expression::LiteralBoolean(false));
                 }
 
-                let Expression::LiteralBoolean(right_bool) = right else { return Err(Errored(LoxError::with_message("Cannot compare unlike types"))); };
+                let Expression::LiteralBoolean(right_bool) = right else { return Err(Errored(LoxError::new(ErrorKind::Type, "Cannot compare unlike types"))); };
 
                 Ok(Expression::LiteralBoolean(b(*left_bool, *right_bool)))
             }
+            Expression::Object(_) if right == &Expression::Nil => Ok(Expression::LiteralBoolean(false)),
             Expression::Nil => {
                 if right == &Expression::Nil {
                     Ok(Expression::LiteralBoolean(true))
@@ -493,11 +1240,11 @@ impl Interpreter {
                     Ok(Expression::LiteralBoolean(false))
                 }
             }
-            _ => Err(Errored(LoxError::with_message("Invalid expression for comparison"))),
+            _ => Err(Errored(LoxError::new(ErrorKind::Type, "Invalid expression for comparison"))),
         }
     }
 
-    fn is_truthy(&self, expr: &Expression) -> bool {
+    pub fn is_truthy(&self, expr: &Expression) -> bool {
         if expr == &Expression::Nil {
             return false;
         }
@@ -0,0 +1,24 @@
+use std::sync::{
+    atomic::{AtomicBool, Ordering},
+    Arc,
+};
+
+/// Stops a running script from another thread, for example on Ctrl-C. The interpreter checks it before every
+/// statement, so loops notice it once per iteration, and the script unwinds with `BreakReason::Interrupted`.
+#[derive(Debug, Clone, Default)]
+pub struct InterruptHandle(Arc<AtomicBool>);
+
+impl InterruptHandle {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn interrupt(&self) {
+        self.0.store(true, Ordering::SeqCst);
+    }
+
+    /// Whether an interrupt is pending. Taking it clears it, so the next script runs normally.
+    pub fn take(&self) -> bool {
+        self.0.swap(false, Ordering::SeqCst)
+    }
+}
@@ -0,0 +1,155 @@
+use std::{cell::RefCell, fmt::Debug, rc::Rc};
+
+use crate::{environment::Identifier, err::{ErrorKind, LoxError}, expr::Expression, interpreter::Interpreter, outcome::Outcome};
+use crate::outcome::BreakReason::Errored;
+
+/// The protocol `for (x in ...)` iterates through. Returning `None` ends the iteration.
+pub trait LoxIterator {
+    fn next(&mut self, interpreter: &mut Interpreter) -> Outcome<Option<Expression>>;
+}
+
+/// A shared handle to an iterator, so it can be stored in variables and passed around as a value
+#[derive(Clone)]
+pub struct IteratorRef(pub Rc<RefCell<dyn LoxIterator>>);
+
+impl IteratorRef {
+    pub fn new<I: LoxIterator + 'static>(iterator: I) -> Self {
+        Self(Rc::new(RefCell::new(iterator)))
+    }
+
+    /// `line` is where the iteration is driven from, like a `for` statement, 0 when that isn't known
+    pub fn next(&self, interpreter: &mut Interpreter, line: usize) -> Outcome<Option<Expression>> {
+        // a generator that tries to advance itself from inside its own body
+        let Ok(mut iterator) = self.0.try_borrow_mut() else {
+            return Err(Errored(LoxError::with_message("Iterator is already running")));
+        };
+
+        // advancing a generator runs its body, which can recurse just like a call
+        interpreter.with_call_depth(line, |i| iterator.next(i))
+    }
+}
+
+impl Debug for IteratorRef {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "<iterator>")
+    }
+}
+
+impl PartialEq for IteratorRef {
+    fn eq(&self, other: &Self) -> bool {
+        Rc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+pub struct RangeIterator {
+    next: i64,
+    end: i64,
+    inclusive: bool,
+}
+
+impl RangeIterator {
+    pub fn new(start: i64, end: i64, inclusive: bool) -> Self {
+        Self {
+            next: start,
+            end,
+            inclusive,
+        }
+    }
+}
+
+impl LoxIterator for RangeIterator {
+    fn next(&mut self, _: &mut Interpreter) -> Outcome<Option<Expression>> {
+        let in_range = if self.inclusive { self.next <= self.end } else { self.next < self.end };
+
+        if !in_range {
+            return Ok(None);
+        }
+
+        let current = self.next;
+
+        // stop instead of overflowing on an inclusive range that ends at i64::MAX
+        match self.next.checked_add(1) {
+            Some(n) => self.next = n,
+            None => self.inclusive = false,
+        }
+
+        Ok(Some(Expression::LiteralInteger(current)))
+    }
+}
+
+pub struct StringIterator {
+    chars: Vec<char>,
+    position: usize,
+}
+
+impl StringIterator {
+    pub fn new(string: &str) -> Self {
+        Self {
+            chars: string.chars().collect(),
+            position: 0,
+        }
+    }
+}
+
+impl LoxIterator for StringIterator {
+    fn next(&mut self, _: &mut Interpreter) -> Outcome<Option<Expression>> {
+        let Some(c) = self.chars.get(self.position) else { return Ok(None); };
+
+        self.position += 1;
+
+        Ok(Some(Expression::LiteralString(c.to_string())))
+    }
+}
+
+/// Iterates over a snapshot of a collection's values
+pub struct ValuesIterator {
+    values: std::vec::IntoIter<Expression>,
+}
+
+impl ValuesIterator {
+    pub fn new(values: Vec<Expression>) -> Self {
+        Self {
+            values: values.into_iter(),
+        }
+    }
+}
+
+impl LoxIterator for ValuesIterator {
+    fn next(&mut self, _: &mut Interpreter) -> Outcome<Option<Expression>> {
+        Ok(self.values.next())
+    }
+}
+
+/// Iterates by calling a zero-argument function until it returns `nil`
+pub struct FunctionIterator {
+    function: Identifier,
+}
+
+impl FunctionIterator {
+    pub fn new(function: Identifier) -> Self {
+        Self { function }
+    }
+}
+
+impl LoxIterator for FunctionIterator {
+    fn next(&mut self, interpreter: &mut Interpreter) -> Outcome<Option<Expression>> {
+        let Some(callable) = interpreter.current_env.borrow().get_callable(&self.function) else {
+            return Err(Errored(LoxError::new(ErrorKind::UndefinedFunction, &format!(
+                "Call to undefined function '{}'",
+                self.function.name
+            ))));
+        };
+
+        if callable.arity() != 0 {
+            return Err(Errored(LoxError::new(ErrorKind::Arity, &format!(
+                "Function '{}' must take no arguments to be iterated over",
+                self.function.name
+            ))));
+        }
+
+        match interpreter.call(&self.function.name, callable, &[], 0)? {
+            Expression::Nil => Ok(None),
+            value => Ok(Some(value)),
+        }
+    }
+}
@@ -1,9 +1,19 @@
 pub mod outcome;
+pub mod collections;
+#[cfg(feature = "serde")]
+pub mod convert;
 pub mod environment;
 pub mod err;
 pub mod expr;
+pub mod generator;
 pub mod interpreter;
+pub mod interrupt;
+pub mod iterator;
+pub mod object;
+pub mod limits;
+pub mod output;
 pub mod parser;
+pub mod resolver;
 pub mod scan;
 pub mod stmt;
 pub mod token;
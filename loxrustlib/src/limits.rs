@@ -0,0 +1,39 @@
+use std::time::Duration;
+
+/// A call depth that fits a main thread's stack even in debug builds, where every Lox call nests several
+/// large interpreter frames on the native stack. It's the same depth clox allows. Without a limit, runaway
+/// recursion overflows the native stack and aborts the process instead of failing with an error.
+pub const RECOMMENDED_MAX_CALL_DEPTH: usize = 64;
+
+/// Bounds on how much work a single `interpret` call may do, so that untrusted scripts can be run safely.
+/// `None` means unlimited. By default only the call depth is limited.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ExecutionLimits {
+    /// How many statements may be executed, loop bodies counting once per iteration
+    pub max_steps: Option<u64>,
+    /// How many function calls may be nested in each other
+    pub max_call_depth: Option<usize>,
+    /// How long the script may run for
+    pub timeout: Option<Duration>,
+}
+
+impl Default for ExecutionLimits {
+    fn default() -> Self {
+        Self {
+            max_steps: None,
+            max_call_depth: Some(RECOMMENDED_MAX_CALL_DEPTH),
+            timeout: None,
+        }
+    }
+}
+
+impl ExecutionLimits {
+    /// No limits at all, for hosts that run scripts on a thread with a stack sized for deep recursion
+    pub fn unlimited() -> Self {
+        Self {
+            max_steps: None,
+            max_call_depth: None,
+            timeout: None,
+        }
+    }
+}
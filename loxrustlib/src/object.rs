@@ -0,0 +1,163 @@
+use std::{
+    any::Any,
+    cell::{Ref, RefCell, RefMut},
+    fmt::Debug,
+    rc::Rc,
+};
+
+use crate::{err::{ErrorKind, LoxError}, expr::Value, iterator::IteratorRef};
+
+/// A Rust value handed to scripts, which they can use through `object.property`, `object.property = value`
+/// and `object.method(args)`. Everything is rejected unless the implementation says otherwise.
+pub trait HostObject: Any {
+    /// The name scripts see in error messages
+    fn type_name(&self) -> &str;
+
+    /// How `print` shows the object
+    fn describe(&self) -> String {
+        format!("<{}>", self.type_name())
+    }
+
+    /// How the object is shown inside its own description, when it contains itself
+    fn describe_cycle(&self) -> String {
+        format!("<{}...>", self.type_name())
+    }
+
+    /// What `for (x in object)` goes through, `None` if the object can't be iterated over
+    fn iterate(&self) -> Option<IteratorRef> {
+        None
+    }
+
+    /// The property and method names scripts can use, for tools like REPL completion. Nothing has to be
+    /// listed, it only helps discovering the object.
+    fn members(&self) -> Vec<String> {
+        Vec::new()
+    }
+
+    /// `None` means there's no such property
+    fn get(&self, name: &str) -> Option<Value> {
+        let _ = name;
+
+        None
+    }
+
+    fn set(&mut self, name: &str, value: Value) -> Result<(), LoxError> {
+        let _ = value;
+
+        Err(LoxError::new(ErrorKind::Host, &format!(
+            "Cannot set property '{}' on {}",
+            name,
+            self.type_name()
+        )))
+    }
+
+    /// Methods check their own arguments, there's no arity to declare up front
+    fn call_method(&mut self, name: &str, args: &[Value]) -> Result<Value, LoxError> {
+        let _ = args;
+
+        Err(LoxError::new(ErrorKind::UndefinedProperty, &format!(
+            "Undefined method '{}' on {}",
+            name,
+            self.type_name()
+        )))
+    }
+}
+
+thread_local! {
+    /// The objects whose descriptions are being built, innermost last, so that one containing itself
+    /// doesn't recurse forever
+    static DESCRIBING: RefCell<Vec<ObjectRef>> = const { RefCell::new(Vec::new()) };
+}
+
+/// A shared handle to a host object, so it can be stored in variables and passed around as a value
+#[derive(Clone)]
+pub struct ObjectRef(Rc<RefCell<dyn HostObject>>);
+
+impl ObjectRef {
+    pub fn new<O: HostObject>(object: O) -> Self {
+        Self(Rc::new(RefCell::new(object)))
+    }
+
+    pub fn type_name(&self) -> String {
+        match self.0.try_borrow() {
+            Ok(object) => object.type_name().to_string(),
+            Err(_) => "object".to_string(),
+        }
+    }
+
+    pub fn describe(&self) -> String {
+        let Ok(object) = self.0.try_borrow() else { return "<object>".to_string() };
+
+        if DESCRIBING.with(|d| d.borrow().contains(self)) {
+            return object.describe_cycle();
+        }
+
+        DESCRIBING.with(|d| d.borrow_mut().push(self.clone()));
+        let description = object.describe();
+        DESCRIBING.with(|d| d.borrow_mut().pop());
+
+        description
+    }
+
+    pub fn iterate(&self) -> Result<IteratorRef, LoxError> {
+        let object = self.borrow()?;
+
+        object
+            .iterate()
+            .ok_or_else(|| LoxError::new(ErrorKind::Type, &format!("Cannot iterate over {}", object.type_name())))
+    }
+
+    /// Empty when the object is in use
+    pub fn members(&self) -> Vec<String> {
+        self.0.try_borrow().map(|o| o.members()).unwrap_or_default()
+    }
+
+    pub fn get(&self, name: &str) -> Result<Option<Value>, LoxError> {
+        Ok(self.borrow()?.get(name))
+    }
+
+    pub fn set(&self, name: &str, value: Value) -> Result<(), LoxError> {
+        self.borrow_mut()?.set(name, value)
+    }
+
+    pub fn call_method(&self, name: &str, args: &[Value]) -> Result<Value, LoxError> {
+        self.borrow_mut()?.call_method(name, args)
+    }
+
+    /// Gets the Rust value back out, if it's a `T`
+    pub fn downcast_ref<T: HostObject>(&self) -> Option<Ref<'_, T>> {
+        let object = self.0.try_borrow().ok()?;
+
+        Ref::filter_map(object, |o| (o as &dyn Any).downcast_ref::<T>()).ok()
+    }
+
+    pub fn downcast_mut<T: HostObject>(&self) -> Option<RefMut<'_, T>> {
+        let object = self.0.try_borrow_mut().ok()?;
+
+        RefMut::filter_map(object, |o| (o as &mut dyn Any).downcast_mut::<T>()).ok()
+    }
+
+    fn borrow(&self) -> Result<Ref<'_, dyn HostObject>, LoxError> {
+        self.0
+            .try_borrow()
+            .map_err(|_| LoxError::with_message("Object is already in use"))
+    }
+
+    fn borrow_mut(&self) -> Result<RefMut<'_, dyn HostObject>, LoxError> {
+        self.0
+            .try_borrow_mut()
+            .map_err(|_| LoxError::with_message("Object is already in use"))
+    }
+}
+
+impl Debug for ObjectRef {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "<{}>", self.type_name())
+    }
+}
+
+impl PartialEq for ObjectRef {
+    fn eq(&self, other: &Self) -> bool {
+        Rc::ptr_eq(&self.0, &other.0)
+    }
+}
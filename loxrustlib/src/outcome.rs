@@ -7,7 +7,19 @@ pub type Outcome<T> = Result<T, BreakReason>;
 #[derive(Clone)]
 pub enum BreakReason {
     Errored(LoxError),
-    Returned(Expression)
+    Returned(Expression),
+    /// The script was stopped through an `InterruptHandle`
+    Interrupted,
+}
+
+impl BreakReason {
+    /// Fills in the line of errors raised without knowing where in the script they happened
+    pub fn or_line(self, line: usize) -> Self {
+        match self {
+            BreakReason::Errored(e) => BreakReason::Errored(e.or_line(line)),
+            other => other,
+        }
+    }
 }
 
 impl Display for BreakReason {
@@ -15,6 +27,7 @@ impl Display for BreakReason {
         match self {
             BreakReason::Errored(e) => write!(f, "{}", e),
             BreakReason::Returned(r) => write!(f, "{}", r),
+            BreakReason::Interrupted => write!(f, "Interrupted"),
         }
     }
 }
\ No newline at end of file
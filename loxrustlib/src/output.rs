@@ -0,0 +1,93 @@
+use std::{
+    cell::RefCell,
+    io::{self, Write},
+    rc::Rc,
+};
+
+/// Where the interpreter sends what a script prints, and any diagnostics of its own
+pub trait Output {
+    fn print(&mut self, text: &str) -> io::Result<()>;
+
+    fn error(&mut self, text: &str) -> io::Result<()>;
+}
+
+/// Prints to stdout, and diagnostics to stderr. This is what an `Interpreter` starts out with.
+#[derive(Default)]
+pub struct StdOutput {}
+
+impl Output for StdOutput {
+    fn print(&mut self, text: &str) -> io::Result<()> {
+        writeln!(io::stdout(), "{}", text)
+    }
+
+    fn error(&mut self, text: &str) -> io::Result<()> {
+        writeln!(io::stderr(), "{}", text)
+    }
+}
+
+/// Sends both channels to a single writer, like a file or a socket
+pub struct WriterOutput<W: Write> {
+    writer: W,
+}
+
+impl<W: Write> WriterOutput<W> {
+    pub fn new(writer: W) -> Self {
+        Self { writer }
+    }
+
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+}
+
+impl<W: Write> Output for WriterOutput<W> {
+    fn print(&mut self, text: &str) -> io::Result<()> {
+        writeln!(self.writer, "{}", text)
+    }
+
+    fn error(&mut self, text: &str) -> io::Result<()> {
+        writeln!(self.writer, "{}", text)
+    }
+}
+
+/// Keeps everything in memory. Clones share the same buffers, so one can be handed to the interpreter
+/// and the other kept to read back what was written.
+#[derive(Clone, Default)]
+pub struct CapturedOutput {
+    printed: Rc<RefCell<String>>,
+    errors: Rc<RefCell<String>>,
+}
+
+impl CapturedOutput {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn printed(&self) -> String {
+        self.printed.borrow().clone()
+    }
+
+    pub fn errors(&self) -> String {
+        self.errors.borrow().clone()
+    }
+}
+
+impl Output for CapturedOutput {
+    fn print(&mut self, text: &str) -> io::Result<()> {
+        let mut printed = self.printed.borrow_mut();
+
+        printed.push_str(text);
+        printed.push('\n');
+
+        Ok(())
+    }
+
+    fn error(&mut self, text: &str) -> io::Result<()> {
+        let mut errors = self.errors.borrow_mut();
+
+        errors.push_str(text);
+        errors.push('\n');
+
+        Ok(())
+    }
+}
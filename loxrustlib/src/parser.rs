@@ -1,21 +1,23 @@
 use std::{iter::Peekable, mem};
 
 use crate::{
-    err::LoxError,
+    err::{ErrorKind, LoxError},
     expr::{BinaryOperator, Expression, LogicalOperator, UnaryOperator},
     scan::Scanner,
-    stmt::Statement,
+    stmt::{MatchArm, Pattern, Statement},
     token::{Token, TokenKind}
 };
 
 pub struct Parser<'a> {
     scanner: Peekable<Scanner<'a>>,
+    in_generator: bool,
 }
 
 impl<'a> Parser<'a> {
     pub fn new(scanner: Scanner<'a>) -> Self {
         Self {
             scanner: scanner.peekable(),
+            in_generator: false,
         }
     }
 
@@ -46,38 +48,65 @@ impl<'a> Parser<'a> {
     }
 
     fn consume_next(&mut self, expected_kind: &TokenKind) -> Result<Token, LoxError> {
-        let Some(Ok(Token { kind, line, .. })) = self.scanner.peek() else { return Err(LoxError::with_message("Unexpected end of scan")) };
+        let token = self.advance()?;
 
-        if mem::discriminant(kind) == mem::discriminant(expected_kind) {
-            let Some(Ok(token)) = self.scanner.next() else { return Err(LoxError::with_message("Token is of unexpected kind")); };
-
-            // if-let with && is not supported. Bummer.
+        if mem::discriminant(&token.kind) == mem::discriminant(expected_kind) {
             return Ok(token);
         }
 
-        Err(LoxError::with_message_line(
-            format!(
-                "Expected token of kind '{}', instead got {}",
-                expected_kind, kind
-            ),
-            *line,
+        // the expected kind's payload is only a placeholder, name the kind instead
+        let expected = match expected_kind {
+            TokenKind::Identifier(_) => "Identifier".to_string(),
+            kind => kind.to_string(),
+        };
+
+        Err(error_at(
+            &token,
+            &format!("Expected token of kind '{}', instead got {}", expected, describe(&token)),
         ))
     }
 
+    /// Consumes the next token, whatever it is. Scanning errors are passed on as they are.
+    fn advance(&mut self) -> Result<Token, LoxError> {
+        match self.scanner.next() {
+            Some(Ok(token)) => Ok(token),
+            Some(Err(e)) => Err(e),
+            None => Err(LoxError::new(ErrorKind::Parse, "Unexpected end of input")),
+        }
+    }
+
+    /// An error at the token about to be parsed, the one that doesn't fit. Scanning errors are passed on
+    /// as they are.
+    fn unexpected(&mut self, message: &str) -> LoxError {
+        match self.scanner.peek() {
+            Some(Ok(token)) => error_at(token, &format!("{}, got {}", message, describe(token))),
+            Some(Err(e)) => e.clone(),
+            None => LoxError::new(ErrorKind::Parse, message),
+        }
+    }
+
     fn check_next(&mut self, expected_kind: &TokenKind) -> bool {
         let Some(Ok(Token { kind, .. })) = self.scanner.peek() else { return false; };
 
         expected_kind == kind
     }
 
+    /// Like `match_peeked_token`, but for the token after the next one. Doesn't consume anything.
+    fn check_second(&self, expected_kind: &TokenKind) -> bool {
+        let mut lookahead = self.scanner.clone();
+        lookahead.next();
+
+        let Some(Ok(Token { kind, .. })) = lookahead.peek() else { return false; };
+
+        mem::discriminant(kind) == mem::discriminant(expected_kind)
+    }
+
     fn parse_token_as_unary_op(&self, token: &Token) -> Result<UnaryOperator, LoxError> {
         match token.kind {
             TokenKind::Bang => Ok(UnaryOperator::Not),
             TokenKind::Minus => Ok(UnaryOperator::Minus),
-            _ => Err(LoxError::with_message_line(
-                format!("Expected unary operator, got {}", token.kind),
-                token.line,
-            )),
+            TokenKind::Tilde => Ok(UnaryOperator::BitwiseNot),
+            _ => Err(error_at(token, &format!("Expected unary operator, got {}", describe(token)))),
         }
     }
 
@@ -93,10 +122,15 @@ impl<'a> Parser<'a> {
             TokenKind::Less => Ok(BinaryOperator::LessThan),
             TokenKind::Slash => Ok(BinaryOperator::Division),
             TokenKind::Star => Ok(BinaryOperator::Multiplication),
-            _ => Err(LoxError::with_message_line(
-                format!("Expected binary operator, got {}", token.kind),
-                token.line,
-            )),
+            TokenKind::TildeSlash => Ok(BinaryOperator::IntegerDivision),
+            TokenKind::Percent => Ok(BinaryOperator::Modulo),
+            TokenKind::StarStar => Ok(BinaryOperator::Power),
+            TokenKind::Ampersand => Ok(BinaryOperator::BitwiseAnd),
+            TokenKind::Pipe => Ok(BinaryOperator::BitwiseOr),
+            TokenKind::Caret => Ok(BinaryOperator::BitwiseXor),
+            TokenKind::LessLess => Ok(BinaryOperator::ShiftLeft),
+            TokenKind::GreaterGreater => Ok(BinaryOperator::ShiftRight),
+            _ => Err(error_at(token, &format!("Expected binary operator, got {}", describe(token)))),
         }
     }
 
@@ -104,10 +138,7 @@ impl<'a> Parser<'a> {
         match token.kind {
             TokenKind::And => Ok(LogicalOperator::And),
             TokenKind::Or => Ok(LogicalOperator::Or),
-            _ => Err(LoxError::with_message_line(
-                format!("Expected logical operator, got {}", token.kind),
-                token.line,
-            )),
+            _ => Err(error_at(token, &format!("Expected logical operator, got {}", describe(token)))),
         }
     }
 
@@ -123,17 +154,38 @@ impl<'a> Parser<'a> {
                 break;
             }
 
-            result.push(self.declaration()?);
+            result.push(self.declaration().map_err(|e| e.or_kind(ErrorKind::Parse))?);
         }
 
         Ok(result)
     }
 
+    /// Parses a lone expression, like one typed into a REPL, with nothing after it but an optional semicolon
+    pub fn parse_expression(&mut self) -> Result<Expression, LoxError> {
+        let expression = self.expression().map_err(|e| e.or_kind(ErrorKind::Parse))?;
+
+        self.match_next_kind(&[TokenKind::Semicolon]);
+
+        match self.scanner.peek() {
+            Some(Ok(Token {
+                kind: TokenKind::Eof,
+                ..
+            }))
+            | None => Ok(expression),
+            Some(Err(e)) => Err(e.clone()),
+            Some(Ok(t)) => Err(error_at(t, &format!("Unexpected {} after expression", describe(t)))),
+        }
+    }
+
     fn declaration(&mut self) -> Result<Statement, LoxError> {
         if let Some(TokenKind::Var) = self.match_next_kind(&[TokenKind::Var]) {
             return self.variable_declaration_statement();
         }
 
+        if let Some(TokenKind::Const) = self.match_next_kind(&[TokenKind::Const]) {
+            return self.const_declaration_statement();
+        }
+
         if let Some(TokenKind::Fun) = self.match_next_kind(&[TokenKind::Fun]) {
             return self.function_declaration();
         }
@@ -143,7 +195,7 @@ impl<'a> Parser<'a> {
 
     fn variable_declaration_statement(&mut self) -> Result<Statement, LoxError> {
         let Some(identifier) = self.match_next_token(&[TokenKind::Identifier(String::default())]) else {
-            return Err(LoxError::with_message("Expected variable identifier.")); 
+            return Err(self.unexpected("Expected variable identifier"));
         };
 
         let mut initializer = None;
@@ -160,7 +212,26 @@ impl<'a> Parser<'a> {
         })
     }
 
+    fn const_declaration_statement(&mut self) -> Result<Statement, LoxError> {
+        let identifier = self.consume_next(&TokenKind::Identifier(String::default()))?;
+
+        if self.match_next_token(&[TokenKind::Equal]).is_none() {
+            return Err(error_at(&identifier, &format!("Constant '{}' must be initialized", identifier.lexeme)));
+        }
+
+        let initializer = self.expression()?;
+
+        self.consume_next(&TokenKind::Semicolon)?;
+
+        Ok(Statement::ConstDeclaration {
+            identifier,
+            initializer,
+        })
+    }
+
     fn function_declaration(&mut self) -> Result<Statement, LoxError> {
+        let is_generator = self.match_next_token(&[TokenKind::Star]).is_some();
+
         let identifier = self.consume_next(&TokenKind::Identifier(String::default()))?;
 
         self.consume_next(&TokenKind::LeftParen)?;
@@ -170,10 +241,7 @@ impl<'a> Parser<'a> {
         if !self.check_next(&TokenKind::RightParen) {
             loop {
                 if parameters.len() >= 255 {
-                    return Err(LoxError::with_line(
-                        "Cannot have more than 255 parameters.",
-                        identifier.line,
-                    ));
+                    return Err(self.unexpected("Cannot have more than 255 parameters"));
                 }
 
                 let t = self.consume_next(&TokenKind::Identifier(String::default()))?;
@@ -186,51 +254,65 @@ impl<'a> Parser<'a> {
             }
         }
 
-        let Some(_) = self.match_next_token(&[TokenKind::RightParen]) else { return Err(LoxError::with_line("Expected closing parenthesis", identifier.line))};
+        let Some(_) = self.match_next_token(&[TokenKind::RightParen]) else { return Err(self.unexpected("Expected closing parenthesis")) };
 
         self.consume_next(&TokenKind::LeftBrace)?;
 
-        let body = self.block_statement()?;
+        let enclosing_generator = mem::replace(&mut self.in_generator, is_generator);
+        let body = self.block_statement();
+        self.in_generator = enclosing_generator;
 
         //self.consume_next(&TokenKind::Semicolon)?;
 
-        Ok(Statement::FunDeclaration { name: identifier, parameters, body })
+        Ok(Statement::FunDeclaration { name: identifier, parameters, body: body?, is_generator })
     }
 
     fn statement(&mut self) -> Result<Statement, LoxError> {
-        if let Some(TokenKind::Print) = self.match_next_kind(&[TokenKind::Print]) {
-            return self.print_statement();
+        if let Some(t) = self.match_next_token(&[TokenKind::Print]) {
+            return self.print_statement(&t);
         }
 
         if let Some(TokenKind::LeftBrace) = self.match_next_kind(&[TokenKind::LeftBrace]) {
             return Ok(Statement::BlockStatement { statements: self.block_statement()? });
         }
 
-        if let Some(TokenKind::If) = self.match_next_kind(&[TokenKind::If]) {
-            return self.if_statement();
+        if let Some(t) = self.match_next_token(&[TokenKind::If]) {
+            return self.if_statement(&t);
+        }
+
+        if let Some(t) = self.match_next_token(&[TokenKind::While]) {
+            return self.while_statement(&t);
         }
 
-        if let Some(TokenKind::While) = self.match_next_kind(&[TokenKind::While]) {
-            return self.while_statement();
+        if let Some(t) = self.match_next_token(&[TokenKind::For]) {
+            return self.for_statement(&t);
         }
 
-        if let Some(TokenKind::For) = self.match_next_kind(&[TokenKind::For]) {
-            return self.for_statement();
+        if let Some(t) = self.match_next_token(&[TokenKind::Do]) {
+            return self.do_while_statement(&t);
         }
 
         if let Some(t) = self.match_next_token(&[TokenKind::Return]) {
             return self.return_statement(&t);
         }
 
+        if let Some(t) = self.match_next_token(&[TokenKind::Yield]) {
+            return self.yield_statement(&t);
+        }
+
+        if let Some(t) = self.match_next_token(&[TokenKind::Match]) {
+            return self.match_statement(&t);
+        }
+
         self.expression_statement()
     }
 
-    fn print_statement(&mut self) -> Result<Statement, LoxError> {
+    fn print_statement(&mut self, token: &Token) -> Result<Statement, LoxError> {
         let value = self.expression()?;
 
         self.consume_next(&TokenKind::Semicolon)?;
 
-        Ok(Statement::PrintStatement { printable: value })
+        Ok(Statement::PrintStatement { printable: value, line: token.line })
     }
 
     fn return_statement(&mut self, token: &Token) -> Result<Statement, LoxError> {
@@ -241,7 +323,19 @@ impl<'a> Parser<'a> {
         Ok(Statement::ReturnStatement { keyword: token.clone(), value })
     }
 
-    fn if_statement(&mut self) -> Result<Statement, LoxError> {
+    fn yield_statement(&mut self, token: &Token) -> Result<Statement, LoxError> {
+        if !self.in_generator {
+            return Err(error_at(token, "Cannot yield outside of a generator function"));
+        }
+
+        let value = self.expression()?;
+
+        self.consume_next(&TokenKind::Semicolon)?;
+
+        Ok(Statement::YieldStatement { keyword: token.clone(), value })
+    }
+
+    fn if_statement(&mut self, token: &Token) -> Result<Statement, LoxError> {
         self.consume_next(&TokenKind::LeftParen)?;
 
         let condition = self.expression()?;
@@ -259,10 +353,176 @@ impl<'a> Parser<'a> {
             condition,
             true_branch: Box::new(true_statement),
             else_branch: else_statement,
+            line: token.line,
         })
     }
 
-    fn while_statement(&mut self) -> Result<Statement, LoxError> {
+    fn match_statement(&mut self, keyword: &Token) -> Result<Statement, LoxError> {
+        self.consume_next(&TokenKind::LeftParen)?;
+
+        let subject = self.expression()?;
+
+        self.consume_next(&TokenKind::RightParen)?;
+        self.consume_next(&TokenKind::LeftBrace)?;
+
+        let mut arms = Vec::new();
+        let mut catch_all_line = None;
+
+        while !self.check_next(&TokenKind::RightBrace) {
+            let arm_token = self.scanner.peek().and_then(|t| t.as_ref().ok()).unwrap_or(keyword).clone();
+            let arm_line = arm_token.line;
+
+            if let Some(line) = catch_all_line {
+                return Err(error_at(
+                    &arm_token,
+                    &format!("Unreachable match arm, the arm at line {} already matches every value", line),
+                ));
+            }
+
+            let mut patterns = vec![self.pattern()?];
+
+            while self.match_next_token(&[TokenKind::Pipe]).is_some() {
+                patterns.push(self.pattern()?);
+            }
+
+            if patterns.len() > 1 && patterns.iter().any(|p| matches!(p, Pattern::Wildcard) || !p.bindings().is_empty()) {
+                return Err(error_at(
+                    &arm_token,
+                    "Wildcard and binding patterns can't be combined with other patterns using '|'",
+                ));
+            }
+
+            let mut guard = None;
+
+            if self.match_next_token(&[TokenKind::If]).is_some() {
+                guard = Some(self.expression()?);
+            }
+
+            if guard.is_none() && matches!(patterns[..], [Pattern::Wildcard] | [Pattern::Binding(_)]) {
+                catch_all_line = Some(arm_line);
+            }
+
+            self.consume_next(&TokenKind::FatArrow)?;
+
+            let body = self.statement()?;
+
+            self.match_next_token(&[TokenKind::Comma]);
+
+            arms.push(MatchArm {
+                patterns,
+                guard,
+                body: Box::new(body),
+            });
+        }
+
+        self.consume_next(&TokenKind::RightBrace)?;
+
+        Ok(Statement::MatchStatement {
+            keyword: keyword.clone(),
+            subject,
+            arms,
+        })
+    }
+
+    fn pattern(&mut self) -> Result<Pattern, LoxError> {
+        let pattern = self.nested_pattern()?;
+        let bindings = pattern.bindings();
+
+        for (i, name) in bindings.iter().enumerate() {
+            if bindings[..i].iter().any(|b| b.lexeme == name.lexeme) {
+                return Err(error_at(name, &format!("'{}' is bound more than once in the pattern", name.lexeme)));
+            }
+        }
+
+        Ok(pattern)
+    }
+
+    fn nested_pattern(&mut self) -> Result<Pattern, LoxError> {
+        if let Some(t) = self.match_next_token(&[TokenKind::Identifier(String::default())]) {
+            if t.lexeme == "_" {
+                return Ok(Pattern::Wildcard);
+            }
+
+            return Ok(Pattern::Binding(t));
+        }
+
+        if self.match_next_token(&[TokenKind::LeftBracket]).is_some() {
+            let mut items = Vec::new();
+
+            while !self.check_next(&TokenKind::RightBracket) {
+                items.push(self.nested_pattern()?);
+
+                if self.match_next_token(&[TokenKind::Comma]).is_none() {
+                    break;
+                }
+            }
+
+            self.consume_next(&TokenKind::RightBracket)?;
+
+            return Ok(Pattern::List(items));
+        }
+
+        if self.match_next_token(&[TokenKind::LeftBrace]).is_some() {
+            let mut entries = Vec::new();
+
+            while !self.check_next(&TokenKind::RightBrace) {
+                let key = match self.match_next_token(&[TokenKind::Identifier(String::default()), TokenKind::String(String::default())]) {
+                    Some(Token { kind: TokenKind::String(s), .. }) => s,
+                    Some(t) => t.lexeme,
+                    None => return Err(self.unexpected("Expected a key in the map pattern")),
+                };
+
+                self.consume_next(&TokenKind::Colon)?;
+                entries.push((key, self.nested_pattern()?));
+
+                if self.match_next_token(&[TokenKind::Comma]).is_none() {
+                    break;
+                }
+            }
+
+            self.consume_next(&TokenKind::RightBrace)?;
+
+            return Ok(Pattern::Map(entries));
+        }
+
+        let start = self.pattern_literal()?;
+
+        let Some(range_token) = self.match_next_token(&[TokenKind::DotDot, TokenKind::DotDotEqual]) else {
+            return Ok(Pattern::Literal(start));
+        };
+
+        let end = self.pattern_literal()?;
+
+        Ok(Pattern::Range {
+            start,
+            end,
+            inclusive: range_token.kind == TokenKind::DotDotEqual,
+        })
+    }
+
+    fn pattern_literal(&mut self) -> Result<Expression, LoxError> {
+        let negated = self.match_next_token(&[TokenKind::Minus]).is_some();
+
+        match self.match_next_token(&[
+            TokenKind::Nil,
+            TokenKind::Boolean(bool::default()),
+            TokenKind::Number(f64::default()),
+            TokenKind::Integer(i64::default()),
+            TokenKind::String(String::default()),
+        ]) {
+            Some(Token { kind: TokenKind::Number(n), .. }) => Ok(Expression::LiteralNumber(if negated { -n } else { n })),
+            Some(Token { kind: TokenKind::Integer(i), .. }) => Ok(Expression::LiteralInteger(if negated { -i } else { i })),
+            Some(t) if !negated => match t.kind {
+                TokenKind::String(s) => Ok(Expression::LiteralString(s)),
+                TokenKind::Boolean(b) => Ok(Expression::LiteralBoolean(b)),
+                _ => Ok(Expression::Nil),
+            },
+            Some(t) => Err(error_at(&t, "Only numbers can be negated in a pattern")),
+            None => Err(self.unexpected("Expected a pattern")),
+        }
+    }
+
+    fn while_statement(&mut self, token: &Token) -> Result<Statement, LoxError> {
         self.consume_next(&TokenKind::LeftParen)?;
 
         let condition = self.expression()?;
@@ -274,12 +534,35 @@ impl<'a> Parser<'a> {
         Ok(Statement::WhileStatement {
             condition,
             body: Box::new(body),
+            line: token.line,
         })
     }
 
-    fn for_statement(&mut self) -> Result<Statement, LoxError> {
+    fn do_while_statement(&mut self, token: &Token) -> Result<Statement, LoxError> {
+        let body = self.statement()?;
+
+        self.consume_next(&TokenKind::While)?;
         self.consume_next(&TokenKind::LeftParen)?;
 
+        let condition = self.expression()?;
+
+        self.consume_next(&TokenKind::RightParen)?;
+        self.consume_next(&TokenKind::Semicolon)?;
+
+        Ok(Statement::DoWhileStatement {
+            body: Box::new(body),
+            condition,
+            line: token.line,
+        })
+    }
+
+    fn for_statement(&mut self, token: &Token) -> Result<Statement, LoxError> {
+        self.consume_next(&TokenKind::LeftParen)?;
+
+        if self.check_second(&TokenKind::In) {
+            return self.for_in_statement();
+        }
+
         let initializer;
 
         if self.check_next(&TokenKind::Semicolon) {
@@ -303,22 +586,23 @@ impl<'a> Parser<'a> {
         let mut increment = None;
 
         if !self.check_next(&TokenKind::RightParen) {
-            increment = Some(self.expression()?);
+            increment = Some((self.next_line(), self.expression()?));
         }
 
         self.consume_next(&TokenKind::RightParen)?;
 
         let mut body = self.statement()?;
 
-        if let Some(incr) = increment {
+        if let Some((line, incr)) = increment {
             body = Statement::BlockStatement {
-                statements: vec![body, Statement::ExpressionStatement { expression: incr }],
+                statements: vec![body, Statement::ExpressionStatement { expression: incr, line }],
             }
         }
 
         body = Statement::WhileStatement {
             condition: condition.map_or(Expression::LiteralBoolean(true), |c| c),
             body: Box::new(body),
+            line: token.line,
         };
 
         if let Some(init) = initializer {
@@ -330,12 +614,28 @@ impl<'a> Parser<'a> {
         Ok(body)
     }
 
+    fn for_in_statement(&mut self) -> Result<Statement, LoxError> {
+        let variable = self.consume_next(&TokenKind::Identifier(String::default()))?;
+
+        self.consume_next(&TokenKind::In)?;
+
+        let iterable = self.expression()?;
+
+        self.consume_next(&TokenKind::RightParen)?;
+
+        let body = self.statement()?;
+
+        Ok(Statement::ForInStatement {
+            variable,
+            iterable,
+            body: Box::new(body),
+        })
+    }
+
     fn block_statement(&mut self) -> Result<Vec<Statement>, LoxError> {
         let mut statements = Vec::new();
 
-        loop {
-            let Some(Ok(Token { kind, .. })) = self.scanner.peek() else { break; };
-
+        while let Some(Ok(Token { kind, .. })) = self.scanner.peek() {
             if kind == &TokenKind::RightBrace {
                 break;
             }
@@ -349,11 +649,20 @@ impl<'a> Parser<'a> {
     }
 
     fn expression_statement(&mut self) -> Result<Statement, LoxError> {
+        let line = self.next_line();
         let value = self.expression()?;
 
         self.consume_next(&TokenKind::Semicolon)?;
 
-        Ok(Statement::ExpressionStatement { expression: value })
+        Ok(Statement::ExpressionStatement { expression: value, line })
+    }
+
+    /// The line of the token about to be parsed, for statements that start without a keyword
+    fn next_line(&mut self) -> usize {
+        match self.scanner.peek() {
+            Some(Ok(token)) => token.line,
+            _ => 0,
+        }
     }
 
     fn expression(&mut self) -> Result<Expression, LoxError> {
@@ -363,9 +672,7 @@ impl<'a> Parser<'a> {
     fn assignment(&mut self) -> Result<Expression, LoxError> {
         let expr = self.or()?;
 
-        let Some(Ok(previous)) = self.scanner.peek().cloned() else { return Err(LoxError::with_message("Invalid assignment expression")); };
-
-        if self.match_next_token(&[TokenKind::Equal]).is_some() {
+        if let Some(equals) = self.match_next_token(&[TokenKind::Equal]) {
             let value = self.assignment()?;
 
             if let Expression::Identifier(v) = expr {
@@ -375,10 +682,15 @@ impl<'a> Parser<'a> {
                 });
             }
 
-            return Err(LoxError::with_line(
-                "Invalid assignment target",
-                previous.line,
-            ));
+            if let Expression::Get { object, name } = expr {
+                return Ok(Expression::Set {
+                    object,
+                    name,
+                    value: Box::new(value),
+                });
+            }
+
+            return Err(error_at(&equals, "Invalid assignment target"));
         }
 
         Ok(expr)
@@ -387,8 +699,7 @@ impl<'a> Parser<'a> {
     fn or(&mut self) -> Result<Expression, LoxError> {
         let mut expr = self.and()?;
 
-        loop {
-            let Some(op_token) = self.match_next_token(&[TokenKind::Or]) else { break; };
+        while let Some(op_token) = self.match_next_token(&[TokenKind::Or]) {
             let operator = self.parse_token_as_logical_op(&op_token)?;
 
             expr = self.comparison().map(|right| Expression::Logical {
@@ -404,8 +715,7 @@ impl<'a> Parser<'a> {
     fn and(&mut self) -> Result<Expression, LoxError> {
         let mut expr = self.equality()?;
 
-        loop {
-            let Some(op_token) = self.match_next_token(&[TokenKind::And]) else { break; };
+        while let Some(op_token) = self.match_next_token(&[TokenKind::And]) {
             let operator = self.parse_token_as_logical_op(&op_token)?;
 
             expr = self.comparison().map(|right| Expression::Logical {
@@ -421,8 +731,7 @@ impl<'a> Parser<'a> {
     fn equality(&mut self) -> Result<Expression, LoxError> {
         let mut expr = self.comparison()?;
 
-        loop {
-            let Some(op_token) = self.match_next_token(&[TokenKind::BangEqual, TokenKind::EqualEqual]) else { break; };
+        while let Some(op_token) = self.match_next_token(&[TokenKind::BangEqual, TokenKind::EqualEqual]) {
             let operator = self.parse_token_as_binary_op(&op_token)?;
 
             expr = self.comparison().map(|right| Expression::Binary {
@@ -436,10 +745,87 @@ impl<'a> Parser<'a> {
     }
 
     fn comparison(&mut self) -> Result<Expression, LoxError> {
+        let mut expr = self.range()?;
+
+        while let Some(op_token) = self.match_next_token(&[TokenKind::Greater, TokenKind::GreaterEqual, TokenKind::Less, TokenKind::LessEqual]) {
+            let operator = self.parse_token_as_binary_op(&op_token)?;
+
+            expr = self.range().map(|right| Expression::Binary {
+                left: Box::new(expr),
+                operator,
+                right: Box::new(right),
+            })?;
+        }
+
+        Ok(expr)
+    }
+
+    fn range(&mut self) -> Result<Expression, LoxError> {
+        let start = self.bitwise_or()?;
+
+        let Some(range_token) = self.match_next_token(&[TokenKind::DotDot, TokenKind::DotDotEqual]) else { return Ok(start); };
+
+        let end = self.bitwise_or()?;
+
+        Ok(Expression::Range {
+            start: Box::new(start),
+            end: Box::new(end),
+            inclusive: range_token.kind == TokenKind::DotDotEqual,
+        })
+    }
+
+    fn bitwise_or(&mut self) -> Result<Expression, LoxError> {
+        let mut expr = self.bitwise_xor()?;
+
+        while let Some(op_token) = self.match_next_token(&[TokenKind::Pipe]) {
+            let operator = self.parse_token_as_binary_op(&op_token)?;
+
+            expr = self.bitwise_xor().map(|right| Expression::Binary {
+                left: Box::new(expr),
+                operator,
+                right: Box::new(right),
+            })?;
+        }
+
+        Ok(expr)
+    }
+
+    fn bitwise_xor(&mut self) -> Result<Expression, LoxError> {
+        let mut expr = self.bitwise_and()?;
+
+        while let Some(op_token) = self.match_next_token(&[TokenKind::Caret]) {
+            let operator = self.parse_token_as_binary_op(&op_token)?;
+
+            expr = self.bitwise_and().map(|right| Expression::Binary {
+                left: Box::new(expr),
+                operator,
+                right: Box::new(right),
+            })?;
+        }
+
+        Ok(expr)
+    }
+
+    fn bitwise_and(&mut self) -> Result<Expression, LoxError> {
+        let mut expr = self.shift()?;
+
+        while let Some(op_token) = self.match_next_token(&[TokenKind::Ampersand]) {
+            let operator = self.parse_token_as_binary_op(&op_token)?;
+
+            expr = self.shift().map(|right| Expression::Binary {
+                left: Box::new(expr),
+                operator,
+                right: Box::new(right),
+            })?;
+        }
+
+        Ok(expr)
+    }
+
+    fn shift(&mut self) -> Result<Expression, LoxError> {
         let mut expr = self.term()?;
 
-        loop {
-            let Some(op_token) = self.match_next_token(&[TokenKind::Greater, TokenKind::GreaterEqual, TokenKind::Less, TokenKind::LessEqual]) else { break; };
+        while let Some(op_token) = self.match_next_token(&[TokenKind::LessLess, TokenKind::GreaterGreater]) {
             let operator = self.parse_token_as_binary_op(&op_token)?;
 
             expr = self.term().map(|right| Expression::Binary {
@@ -455,8 +841,7 @@ impl<'a> Parser<'a> {
     fn term(&mut self) -> Result<Expression, LoxError> {
         let mut expr = self.factor()?;
 
-        loop {
-            let Some(op_token) = self.match_next_token(&[TokenKind::Minus, TokenKind::Plus]) else { break; };
+        while let Some(op_token) = self.match_next_token(&[TokenKind::Minus, TokenKind::Plus]) {
             let operator = self.parse_token_as_binary_op(&op_token)?;
 
             expr = self.factor().map(|right| Expression::Binary {
@@ -472,8 +857,7 @@ impl<'a> Parser<'a> {
     fn factor(&mut self) -> Result<Expression, LoxError> {
         let mut expr = self.unary()?;
 
-        loop {
-            let Some(op_token) = self.match_next_token(&[TokenKind::Slash, TokenKind::Star]) else { break; };
+        while let Some(op_token) = self.match_next_token(&[TokenKind::Slash, TokenKind::Star, TokenKind::TildeSlash, TokenKind::Percent]) {
             let operator = self.parse_token_as_binary_op(&op_token)?;
 
             expr = self.unary().map(|right| Expression::Binary {
@@ -487,10 +871,10 @@ impl<'a> Parser<'a> {
     }
 
     fn unary(&mut self) -> Result<Expression, LoxError> {
-        let Some(op_token) = self.match_next_token(&[TokenKind::Bang, TokenKind::Minus]) else { return self.call(); };
+        let Some(op_token) = self.match_next_token(&[TokenKind::Bang, TokenKind::Minus, TokenKind::Tilde]) else { return self.power(); };
         let operator = self.parse_token_as_unary_op(&op_token)?;
 
-        let right = self.call()?;
+        let right = self.power()?;
 
         Ok(Expression::Unary {
             operator,
@@ -498,20 +882,45 @@ impl<'a> Parser<'a> {
         })
     }
 
+    fn power(&mut self) -> Result<Expression, LoxError> {
+        let expr = self.call()?;
+
+        let Some(op_token) = self.match_next_token(&[TokenKind::StarStar]) else { return Ok(expr); };
+        let operator = self.parse_token_as_binary_op(&op_token)?;
+
+        // the exponent is parsed through unary, which makes `**` right-associative and lets `2 ** -1` work
+        let right = self.unary()?;
+
+        Ok(Expression::Binary {
+            left: Box::new(expr),
+            operator,
+            right: Box::new(right),
+        })
+    }
+
     fn call(&mut self) -> Result<Expression, LoxError> {
         let mut expr = self.primary()?;
 
-        
-        while let Some(opening_paren) = self.match_next_token(&[TokenKind::LeftParen]) {
+        while let Some(opening_paren) = self.match_next_token(&[TokenKind::LeftParen, TokenKind::Dot]) {
+            if opening_paren.kind == TokenKind::Dot {
+                let Some(name) = self.match_next_token(&[TokenKind::Identifier(String::default())]) else {
+                    return Err(self.unexpected("Expected property name after '.'"));
+                };
+
+                expr = Expression::Get {
+                    object: Box::new(expr),
+                    name,
+                };
+
+                continue;
+            }
+
             let mut arguments = Vec::new();
 
             if !self.check_next(&TokenKind::RightParen) {
                 loop {
                     if arguments.len() >= 255 {
-                        return Err(LoxError::with_line(
-                            "Cannot have more than 255 arguments to a call.",
-                            opening_paren.line,
-                        ));
+                        return Err(self.unexpected("Cannot have more than 255 arguments to a call"));
                     }
 
                     arguments.push(self.expression()?);
@@ -522,7 +931,7 @@ impl<'a> Parser<'a> {
                 }
             }
 
-            let Some(t) = self.match_next_token(&[TokenKind::RightParen]) else { return Err(LoxError::with_line("Expected closing parenthesis", opening_paren.line))};
+            let Some(t) = self.match_next_token(&[TokenKind::RightParen]) else { return Err(self.unexpected("Expected closing parenthesis")) };
 
             expr = Expression::Call {
                 callee: Box::new(expr),
@@ -539,17 +948,18 @@ impl<'a> Parser<'a> {
             TokenKind::Nil,
             TokenKind::Boolean(bool::default()),
             TokenKind::Number(f64::default()),
+            TokenKind::Integer(i64::default()),
             TokenKind::String(String::default()),
+            TokenKind::InterpolationStart(String::default()),
             TokenKind::Identifier(String::default()),
             TokenKind::LeftParen,
         ]) {
             Some(Token {
                 kind: TokenKind::LeftParen,
-                line,
                 ..
             }) => {
                 let expr = self.expression()?;
-                let Some(Ok(next_token)) = self.scanner.next() else { return Err(LoxError::with_line("Expected closing parenthesis ')'.", line)) };
+                let next_token = self.advance()?;
 
                 match next_token.kind {
                     TokenKind::RightParen => Ok(Expression::Grouping {
@@ -565,21 +975,21 @@ impl<'a> Parser<'a> {
 
                             expressions.push(next_expr);
 
-                            let Some(Ok(next_token)) = self.scanner.next() else { return Err(LoxError::with_line("Expected comma ',' or closing parenthesis ')'.", line)) };
+                            let next_token = self.advance()?;
 
                             return match next_token.kind {
                                 TokenKind::RightParen => Ok(Expression::Comma { expressions }),
                                 TokenKind::Comma => continue,
-                                _ => Err(LoxError::with_line(
-                                    "Expected comma ',' or closing parenthesis ')'.",
-                                    line,
+                                _ => Err(error_at(
+                                    &next_token,
+                                    &format!("Expected comma ',' or closing parenthesis ')', got {}", describe(&next_token)),
                                 )),
                             };
                         }
                     }
-                    _ => Err(LoxError::with_line(
-                        "Expected closing parenthesis ')'.",
-                        line,
+                    _ => Err(error_at(
+                        &next_token,
+                        &format!("Expected closing parenthesis ')', got {}", describe(&next_token)),
                     )),
                 }
             }
@@ -587,10 +997,19 @@ impl<'a> Parser<'a> {
                 kind: TokenKind::Number(n),
                 ..
             }) => Ok(Expression::LiteralNumber(n)),
+            Some(Token {
+                kind: TokenKind::Integer(i),
+                ..
+            }) => Ok(Expression::LiteralInteger(i)),
             Some(Token {
                 kind: TokenKind::String(s),
                 ..
             }) => Ok(Expression::LiteralString(s)),
+            Some(Token {
+                kind: TokenKind::InterpolationStart(s),
+                line,
+                ..
+            }) => self.interpolation(s, line),
             Some(Token {
                 kind: TokenKind::Boolean(b),
                 ..
@@ -600,11 +1019,61 @@ impl<'a> Parser<'a> {
                 ..
             }) => Ok(Expression::Nil),
             Some(t) if matches!(t.kind, TokenKind::Identifier(_)) => Ok(Expression::Identifier(t)),
-            Some(t) => Err(LoxError::with_line("Unexpected token '{}'.", t.line)),
-            None => Err(LoxError::with_line(
-                &format!("Expected expression. Got {:?}", self.scanner.peek()),
-                0,
-            )),
+            Some(t) => Err(error_at(&t, &format!("Unexpected {}", describe(&t)))),
+            None => Err(self.unexpected("Expected expression")),
+        }
+    }
+
+    fn interpolation(&mut self, head: String, line: usize) -> Result<Expression, LoxError> {
+        let mut parts = vec![Expression::LiteralString(head)];
+
+        loop {
+            parts.push(self.expression()?);
+
+            match self.scanner.next() {
+                Some(Ok(Token {
+                    kind: TokenKind::InterpolationMiddle(s),
+                    ..
+                })) => parts.push(Expression::LiteralString(s)),
+                Some(Ok(Token {
+                    kind: TokenKind::InterpolationEnd(s),
+                    ..
+                })) => {
+                    parts.push(Expression::LiteralString(s));
+                    break;
+                }
+                Some(Err(e)) => return Err(e),
+                Some(Ok(t)) => {
+                    return Err(error_at(
+                        &t,
+                        &format!("Expected closing brace '}}' after interpolated expression, got {}", describe(&t)),
+                    ))
+                }
+                None => {
+                    return Err(LoxError::with_line(
+                        "Expected closing brace '}' after interpolated expression",
+                        line,
+                    ))
+                }
+            }
         }
+
+        // drop the empty text between back-to-back interpolations
+        parts.retain(|p| p != &Expression::LiteralString(String::new()));
+
+        Ok(Expression::Interpolation { parts })
+    }
+}
+
+/// A parse error at `token`, the one the parser couldn't make sense of
+fn error_at(token: &Token, message: &str) -> LoxError {
+    LoxError::new(ErrorKind::Parse, message).or_token(token)
+}
+
+/// How a token is named in error messages
+fn describe(token: &Token) -> String {
+    match token.kind {
+        TokenKind::Eof => "the end of the input".to_string(),
+        _ => format!("'{}'", token.lexeme),
     }
 }
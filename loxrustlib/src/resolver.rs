@@ -0,0 +1,214 @@
+use std::collections::HashMap;
+
+use crate::{
+    err::{ErrorKind, LoxError},
+    expr::Expression,
+    stmt::{MatchArm, Statement},
+    token::Token,
+};
+
+/// A static pass over parsed statements, run before interpreting them. It mirrors the interpreter's scoping
+/// (blocks nest, function bodies only see their own scope and the globals) and rejects assignments to constants.
+/// Anything it can't see, like globals declared in a later REPL line, is still caught by `Environment::assign`.
+pub struct Resolver {
+    scopes: Vec<HashMap<String, bool>>,
+}
+
+impl Default for Resolver {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Resolver {
+    pub fn new() -> Self {
+        Self {
+            scopes: vec![HashMap::new()],
+        }
+    }
+
+    /// The global scope is kept between calls, so a REPL can resolve one line at a time
+    pub fn resolve(&mut self, statements: &[Statement]) -> Result<(), LoxError> {
+        statements
+            .iter()
+            .try_for_each(|s| self.resolve_statement(s))
+            .map_err(|e| e.or_kind(ErrorKind::Resolve))
+    }
+
+    fn resolve_statement(&mut self, statement: &Statement) -> Result<(), LoxError> {
+        match statement {
+            Statement::ExpressionStatement { expression, .. } => self.resolve_expression(expression),
+            Statement::PrintStatement { printable, .. } => self.resolve_expression(printable),
+            Statement::VariableDeclaration {
+                identifier,
+                initializer,
+            } => {
+                if let Some(init) = initializer {
+                    self.resolve_expression(init)?;
+                }
+
+                self.declare(identifier, false)
+            }
+            Statement::ConstDeclaration {
+                identifier,
+                initializer,
+            } => {
+                self.resolve_expression(initializer)?;
+                self.declare(identifier, true)
+            }
+            Statement::BlockStatement { statements } => self.resolve_scoped(&[], |r| r.resolve(statements)),
+            Statement::IfStatement {
+                condition,
+                true_branch,
+                else_branch,
+                ..
+            } => {
+                self.resolve_expression(condition)?;
+                self.resolve_statement(true_branch)?;
+
+                match else_branch {
+                    Some(el) => self.resolve_statement(el),
+                    None => Ok(()),
+                }
+            }
+            Statement::WhileStatement { condition, body, .. } | Statement::DoWhileStatement { body, condition, .. } => {
+                self.resolve_expression(condition)?;
+                self.resolve_statement(body)
+            }
+            Statement::ForInStatement { variable, iterable, body } => {
+                self.resolve_expression(iterable)?;
+                self.resolve_scoped(std::slice::from_ref(variable), |r| r.resolve_statement(body))
+            }
+            Statement::FunDeclaration { name: _, parameters, body, is_generator: _ } => {
+                // function bodies are evaluated in an environment whose parent is the global one
+                let enclosing = self.scopes.split_off(1);
+
+                let result = self.resolve_scoped(parameters, |r| r.resolve(body));
+
+                self.scopes.extend(enclosing);
+
+                result
+            }
+            Statement::ReturnStatement { keyword: _, value } | Statement::YieldStatement { keyword: _, value } => {
+                self.resolve_expression(value)
+            }
+            Statement::MatchStatement { keyword: _, subject, arms } => {
+                self.resolve_expression(subject)?;
+
+                arms.iter().try_for_each(|arm| self.resolve_match_arm(arm))
+            }
+        }
+    }
+
+    fn resolve_match_arm(&mut self, arm: &MatchArm) -> Result<(), LoxError> {
+        let bindings: Vec<Token> = arm.patterns.iter().flat_map(|p| p.bindings()).cloned().collect();
+
+        self.resolve_scoped(&bindings, |r| {
+            if let Some(guard) = &arm.guard {
+                r.resolve_expression(guard)?;
+            }
+
+            r.resolve_statement(&arm.body)
+        })
+    }
+
+    pub fn resolve_expression(&mut self, expression: &Expression) -> Result<(), LoxError> {
+        match expression {
+            Expression::Assignment {
+                identifier,
+                expression,
+            } => {
+                self.resolve_expression(expression)?;
+
+                if self.is_constant(identifier) {
+                    return Err(LoxError::new(
+                        ErrorKind::ConstantAssignment,
+                        &format!("Cannot assign to constant '{}'", identifier.lexeme),
+                    )
+                    .or_token(identifier));
+                }
+
+                Ok(())
+            }
+            Expression::Binary { left, operator: _, right } | Expression::Logical { left, operator: _, right } => {
+                self.resolve_expression(left)?;
+                self.resolve_expression(right)
+            }
+            Expression::Range { start, end, inclusive: _ } => {
+                self.resolve_expression(start)?;
+                self.resolve_expression(end)
+            }
+            Expression::Unary { operator: _, right } => self.resolve_expression(right),
+            Expression::Get { object, name: _ } => self.resolve_expression(object),
+            Expression::Set { object, name: _, value } => {
+                self.resolve_expression(object)?;
+                self.resolve_expression(value)
+            }
+            Expression::Grouping { expression } => self.resolve_expression(expression),
+            Expression::Comma { expressions } | Expression::Interpolation { parts: expressions } => {
+                expressions.iter().try_for_each(|e| self.resolve_expression(e))
+            }
+            Expression::Call {
+                callee,
+                closing_parenthesis: _,
+                arguments,
+            } => {
+                self.resolve_expression(callee)?;
+
+                arguments.iter().try_for_each(|e| self.resolve_expression(e))
+            }
+            Expression::Iterator(_)
+            | Expression::Object(_)
+            | Expression::LiteralNumber(_)
+            | Expression::LiteralInteger(_)
+            | Expression::LiteralBoolean(_)
+            | Expression::LiteralString(_)
+            | Expression::Nil
+            | Expression::Identifier(_) => Ok(()),
+        }
+    }
+
+    fn resolve_scoped<F>(&mut self, variables: &[Token], resolve: F) -> Result<(), LoxError>
+    where
+        F: FnOnce(&mut Self) -> Result<(), LoxError>,
+    {
+        self.scopes.push(HashMap::new());
+
+        for v in variables {
+            // a fresh scope, nothing to clash with
+            let _ = self.declare(v, false);
+        }
+
+        let result = resolve(self);
+
+        self.scopes.pop();
+
+        result
+    }
+
+    /// Redeclaring a constant in the same scope would let it be changed after all, so it's rejected
+    fn declare(&mut self, name: &Token, constant: bool) -> Result<(), LoxError> {
+        let Some(scope) = self.scopes.last_mut() else { return Ok(()) };
+
+        if scope.get(&name.lexeme) == Some(&true) {
+            return Err(LoxError::new(
+                ErrorKind::ConstantAssignment,
+                &format!("Cannot redeclare constant '{}'", name.lexeme),
+            )
+            .or_token(name));
+        }
+
+        scope.insert(name.lexeme.clone(), constant);
+
+        Ok(())
+    }
+
+    fn is_constant(&self, name: &Token) -> bool {
+        self.scopes
+            .iter()
+            .rev()
+            .find_map(|scope| scope.get(&name.lexeme))
+            .copied()
+            .unwrap_or(false)
+    }
+}
@@ -1,19 +1,106 @@
 use std::{iter::Peekable, str::Chars};
 
 use crate::{
-    err::LoxError,
+    err::{ErrorKind, LoxError},
     token::{Token, TokenKind},
 };
+
+/// Every reserved word and the token it scans as
+const KEYWORD_TOKENS: &[(&str, TokenKind)] = &[
+    ("and", TokenKind::And),
+    ("class", TokenKind::Class),
+    ("const", TokenKind::Const),
+    ("do", TokenKind::Do),
+    ("else", TokenKind::Else),
+    ("false", TokenKind::Boolean(false)),
+    ("for", TokenKind::For),
+    ("fun", TokenKind::Fun),
+    ("if", TokenKind::If),
+    ("in", TokenKind::In),
+    ("match", TokenKind::Match),
+    ("nil", TokenKind::Nil),
+    ("or", TokenKind::Or),
+    ("print", TokenKind::Print),
+    ("return", TokenKind::Return),
+    ("super", TokenKind::Super),
+    ("this", TokenKind::This),
+    ("true", TokenKind::Boolean(true)),
+    ("var", TokenKind::Var),
+    ("while", TokenKind::While),
+    ("yield", TokenKind::Yield),
+];
+
+/// The keywords scripts can use, for tools like REPL completion. `class`, `super` and `this` are reserved
+/// for classes but left out, as nothing implements them yet.
+pub fn keywords() -> impl Iterator<Item = &'static str> {
+    KEYWORD_TOKENS
+        .iter()
+        .filter(|(_, kind)| !matches!(kind, TokenKind::Class | TokenKind::Super | TokenKind::This))
+        .map(|(keyword, _)| *keyword)
+}
+
+#[derive(Clone)]
 pub struct Scanner<'a> {
-    reader: Peekable<Chars<'a>>,
+    reader: SourceReader<'a>,
     line: usize,
+    /// The column of the first character of the token being scanned
+    token_column: usize,
+    interpolations: Vec<Interpolation>,
+}
+
+/// The source's characters, along with the column of the last one read
+#[derive(Clone)]
+struct SourceReader<'a> {
+    chars: Peekable<Chars<'a>>,
+    column: usize,
+}
+
+impl<'a> SourceReader<'a> {
+    fn peek(&mut self) -> Option<&char> {
+        self.chars.peek()
+    }
+
+    fn next_if(&mut self, func: impl FnOnce(&char) -> bool) -> Option<char> {
+        match self.chars.peek() {
+            Some(c) if func(c) => self.next(),
+            _ => None,
+        }
+    }
+}
+
+impl<'a> Iterator for SourceReader<'a> {
+    type Item = char;
+
+    fn next(&mut self) -> Option<char> {
+        let c = self.chars.next()?;
+
+        self.column = match c {
+            '\n' => 0,
+            _ => self.column + 1,
+        };
+
+        Some(c)
+    }
+}
+
+/// An open `${ ... }` section of a string literal. `depth` counts the braces opened inside of it,
+/// so the scanner knows which closing brace resumes the surrounding string.
+#[derive(Clone)]
+struct Interpolation {
+    depth: usize,
+    triple_quoted: bool,
 }
 
 impl<'a> Scanner<'a> {
     pub fn new(string: &'a str) -> Self {
         Self {
-            reader: string.chars().peekable(),
+            reader: SourceReader {
+                chars: string.chars().peekable(),
+                column: 0,
+            },
             line: 1,
+            token_column: 0,
+            interpolations: Vec::new(),
         }
     }
 
@@ -24,6 +111,14 @@ impl<'a> Scanner<'a> {
         }
     }
 
+    fn peek_nth(&self, n: usize) -> Option<char> {
+        self.reader.clone().nth(n)
+    }
+
+    fn peek_second_match(&self, expected_char: char) -> bool {
+        self.peek_nth(1) == Some(expected_char)
+    }
+
     fn is_digit(&self, c: char) -> bool {
         c.is_ascii_digit()
     }
@@ -45,17 +140,69 @@ impl<'a> Scanner<'a> {
     }
 
     fn create_string_token(&mut self) -> Result<Token, LoxError> {
+        // a third quote right after an empty string opens a triple-quoted string
+        let triple_quoted = self.peek_match('"') && self.peek_second_match('"');
+
+        if triple_quoted {
+            self.reader.next();
+            self.reader.next();
+        }
+
+        self.create_string_segment_token(triple_quoted, false)
+    }
+
+    /// Scans string contents up to the closing quote or the next `${`. A string without interpolations
+    /// is a single `String` token, otherwise it is split into `InterpolationStart`, any number of
+    /// `InterpolationMiddle` and a final `InterpolationEnd` token, with the embedded expressions in between.
+    fn create_string_segment_token(&mut self, triple_quoted: bool, continued: bool) -> Result<Token, LoxError> {
         let mut buf: Vec<char> = Vec::new();
 
-        loop {
+        let interpolated = loop {
             match self.reader.next() {
-                Some('"') => break,
-                Some('\\') => {
-                    if self.reader.peek() == Some(&'"') {
-                        buf.push('"');
-                        self.reader.next();
+                Some('"') if !triple_quoted => break false,
+                Some('"') if self.peek_match('"') && self.peek_second_match('"') => {
+                    self.reader.next();
+                    self.reader.next();
+                    break false;
+                }
+                Some('$') if self.peek_match('{') => {
+                    self.reader.next();
+                    break true;
+                }
+                Some('\\') => buf.push(self.read_escape_sequence()?),
+                Some(c) => {
+                    if c == '\n' {
+                        self.line += 1;
                     }
+
+                    buf.push(c)
                 }
+                None => return Err(Self::unterminated("string", self.line)),
+            }
+        };
+
+        if interpolated {
+            self.interpolations.push(Interpolation { depth: 0, triple_quoted });
+        }
+
+        let lexeme = String::from_iter(buf);
+
+        let kind = match (continued, interpolated) {
+            (false, false) => TokenKind::String(lexeme.clone()),
+            (false, true) => TokenKind::InterpolationStart(lexeme.clone()),
+            (true, true) => TokenKind::InterpolationMiddle(lexeme.clone()),
+            (true, false) => TokenKind::InterpolationEnd(lexeme.clone()),
+        };
+
+        Ok(Token::new(kind, lexeme, self.line))
+    }
+
+    fn create_raw_string_token(&mut self) -> Result<Token, LoxError> {
+        let mut buf: Vec<char> = Vec::new();
+
+        loop {
+            match self.reader.next() {
+                Some('"') => break,
                 Some(c) => {
                     if c == '\n' {
                         self.line += 1;
@@ -63,7 +210,7 @@ impl<'a> Scanner<'a> {
 
                     buf.push(c)
                 }
-                None => return Err(LoxError::with_line("Unterminated string", self.line)),
+                None => return Err(Self::unterminated("string", self.line)),
             }
         }
 
@@ -76,27 +223,191 @@ impl<'a> Scanner<'a> {
         ))
     }
 
+    fn read_escape_sequence(&mut self) -> Result<char, LoxError> {
+        match self.reader.next() {
+            Some('n') => Ok('\n'),
+            Some('t') => Ok('\t'),
+            Some('r') => Ok('\r'),
+            Some('0') => Ok('\0'),
+            Some('\\') => Ok('\\'),
+            Some('"') => Ok('"'),
+            Some('$') => Ok('$'),
+            Some('x') => {
+                let mut digits = String::new();
+
+                for _ in 0..2 {
+                    match self.reader.peek().cloned() {
+                        Some(c) if c.is_ascii_hexdigit() => {
+                            digits.push(c);
+                            self.reader.next();
+                        }
+                        _ => break,
+                    }
+                }
+
+                if digits.len() != 2 {
+                    return Err(LoxError::with_line(
+                        "Invalid escape sequence, '\\x' must be followed by two hex digits",
+                        self.line,
+                    ));
+                }
+
+                // two hex digits always fit into a char, so this can't fail
+                Ok(char::from(u8::from_str_radix(&digits, 16).unwrap()))
+            }
+            Some('u') => {
+                if !self.peek_match('{') {
+                    return Err(LoxError::with_line(
+                        "Invalid unicode escape, expected '{' after '\\u'",
+                        self.line,
+                    ));
+                }
+
+                self.reader.next();
+
+                let mut digits = String::new();
+
+                loop {
+                    match self.reader.next() {
+                        Some('}') => break,
+                        Some(c) if c.is_ascii_hexdigit() && digits.len() < 6 => digits.push(c),
+                        _ => {
+                            return Err(LoxError::with_line(
+                                "Invalid unicode escape, expected 1 to 6 hex digits followed by '}'",
+                                self.line,
+                            ))
+                        }
+                    }
+                }
+
+                u32::from_str_radix(&digits, 16)
+                    .ok()
+                    .and_then(char::from_u32)
+                    .ok_or_else(|| {
+                        LoxError::with_message_line(
+                            format!("Invalid unicode escape '\\u{{{}}}'", digits),
+                            self.line,
+                        )
+                    })
+            }
+            Some(other_char) => Err(LoxError::with_message_line(
+                format!("Invalid escape sequence '\\{}'", other_char),
+                self.line,
+            )),
+            None => Err(Self::unterminated("string", self.line)),
+        }
+    }
+
     fn create_number_token(&mut self, starting_digit: char) -> Result<Token, LoxError> {
         let mut buf: Vec<char> = Vec::new();
         buf.push(starting_digit);
 
+        if starting_digit == '0' {
+            let radix = match self.reader.peek() {
+                Some('x') | Some('X') => Some(16),
+                Some('o') | Some('O') => Some(8),
+                Some('b') | Some('B') => Some(2),
+                _ => None,
+            };
+
+            if let Some(radix) = radix {
+                return self.create_radix_number_token(radix);
+            }
+        }
+
+        self.read_digits(&mut buf, 10)?;
+
+        let mut is_integer = true;
+
+        // only treat the dot as a decimal point if a digit follows, so that `1..2` and `1.abs` still scan
+        if self.peek_match('.') && self.peek_nth(1).is_some_and(|c| self.is_digit(c)) {
+            buf.push('.');
+            self.reader.next();
+            is_integer = false;
+
+            self.read_digits(&mut buf, 10)?;
+        }
+
+        let has_exponent = match (self.reader.peek().cloned(), self.peek_nth(1), self.peek_nth(2)) {
+            (Some('e') | Some('E'), Some(d), _) if self.is_digit(d) => true,
+            (Some('e') | Some('E'), Some('+') | Some('-'), Some(d)) if self.is_digit(d) => true,
+            _ => false,
+        };
+
+        if has_exponent {
+            buf.push(self.reader.next().unwrap());
+            is_integer = false;
+
+            if let Some(sign) = self.reader.next_if(|c| *c == '+' || *c == '-') {
+                buf.push(sign);
+            }
+
+            self.read_digits(&mut buf, 10)?;
+        }
+
+        let lexeme = String::from_iter(buf);
+        let digits = lexeme.replace('_', "");
+
+        if is_integer {
+            let value = digits.parse().map_err(|_| {
+                LoxError::with_message_line(format!("Integer literal '{}' is out of range", lexeme), self.line)
+            })?;
+
+            return Ok(Token::new(TokenKind::Integer(value), lexeme, self.line));
+        }
+
+        let value = digits.parse().map_err(|_| {
+            LoxError::with_message_line(format!("Invalid number literal '{}'", lexeme), self.line)
+        })?;
+
+        Ok(Token::new(TokenKind::Number(value), lexeme, self.line))
+    }
+
+    fn create_radix_number_token(&mut self, radix: u32) -> Result<Token, LoxError> {
+        let mut buf: Vec<char> = vec!['0'];
+        buf.extend(self.reader.next());
+
+        if !self.reader.peek().is_some_and(|c| c.is_digit(radix)) {
+            return Err(LoxError::with_message_line(
+                format!("Expected digits after '{}'", String::from_iter(&buf)),
+                self.line,
+            ));
+        }
+
+        self.read_digits(&mut buf, radix)?;
+
+        let lexeme = String::from_iter(buf);
+        let digits = lexeme[2..].replace('_', "");
+
+        let value = i64::from_str_radix(&digits, radix).map_err(|_| {
+            LoxError::with_message_line(format!("Integer literal '{}' is out of range", lexeme), self.line)
+        })?;
+
+        Ok(Token::new(TokenKind::Integer(value), lexeme, self.line))
+    }
+
+    /// Consumes digits of the given radix into `buf`, allowing single `_` separators between them.
+    fn read_digits(&mut self, buf: &mut Vec<char>, radix: u32) -> Result<(), LoxError> {
         loop {
             match self.reader.peek().cloned() {
-                Some(c) if self.is_digit(c) || c == '.' => {
+                Some(c) if c.is_digit(radix) => {
                     buf.push(c);
                     self.reader.next();
                 }
-                Some(_) | None => break,
+                Some('_') => {
+                    if !self.peek_nth(1).is_some_and(|c| c.is_digit(radix)) {
+                        return Err(LoxError::with_line(
+                            "A digit separator '_' must be followed by a digit",
+                            self.line,
+                        ));
+                    }
+
+                    buf.push('_');
+                    self.reader.next();
+                }
+                Some(_) | None => return Ok(()),
             }
         }
-
-        let lexeme = String::from_iter(buf);
-
-        Ok(Token::new(
-            TokenKind::Number(lexeme.parse().unwrap()),
-            lexeme,
-            self.line,
-        ))
     }
 
     fn create_identifier_token(&mut self, alpha_char: char) -> Result<Token, LoxError> {
@@ -122,80 +433,149 @@ impl<'a> Scanner<'a> {
     }
 
     fn match_keyword(&self, identifier: String) -> Option<Token> {
-        match identifier.as_str() {
-            "and" => Some(self.create_token(TokenKind::And, identifier)),
-            "class" => Some(self.create_token(TokenKind::Class, identifier)),
-            "else" => Some(self.create_token(TokenKind::Else, identifier)),
-            "false" => Some(self.create_token(TokenKind::Boolean(false), identifier)),
-            "for" => Some(self.create_token(TokenKind::For, identifier)),
-            "fun" => Some(self.create_token(TokenKind::Fun, identifier)),
-            "if" => Some(self.create_token(TokenKind::If, identifier)),
-            "nil" => Some(self.create_token(TokenKind::Nil, identifier)),
-            "or" => Some(self.create_token(TokenKind::Or, identifier)),
-            "print" => Some(self.create_token(TokenKind::Print, identifier)),
-            "super" => Some(self.create_token(TokenKind::Super, identifier)),
-            "this" => Some(self.create_token(TokenKind::This, identifier)),
-            "true" => Some(self.create_token(TokenKind::Boolean(true), identifier)),
-            "var" => Some(self.create_token(TokenKind::Var, identifier)),
-            "while" => Some(self.create_token(TokenKind::While, identifier)),
-            "return" => Some(self.create_token(TokenKind::Return, identifier)),
-            _ => None,
+        KEYWORD_TOKENS
+            .iter()
+            .find(|(keyword, _)| *keyword == identifier)
+            .map(|(_, kind)| self.create_token(kind.clone(), identifier))
+    }
+
+    /// Skips past the end of a `/* ... */` comment whose opening has already been consumed.
+    /// Block comments nest, so every `/*` inside of it needs its own `*/`.
+    fn skip_block_comment(&mut self) -> Result<(), LoxError> {
+        let starting_line = self.line;
+        let mut depth = 1;
+
+        while depth > 0 {
+            match self.reader.next() {
+                Some('/') if self.peek_match('*') => {
+                    self.reader.next();
+                    depth += 1;
+                }
+                Some('*') if self.peek_match('/') => {
+                    self.reader.next();
+                    depth -= 1;
+                }
+                Some('\n') => self.line += 1,
+                Some(_) => continue,
+                None => return Err(Self::unterminated("block comment", starting_line)),
+            }
         }
+
+        Ok(())
+    }
+
+    fn unterminated(what: &str, line: usize) -> LoxError {
+        LoxError::with_message_line(format!("Unterminated {}", what), line).of_kind(ErrorKind::Unterminated)
     }
 
     fn match_next_token(&mut self) -> Option<Result<Token, LoxError>> {
         loop {
             let c = self.reader.next();
+            self.token_column = self.reader.column;
 
             let token = match c {
                 Some('(') => self.create_token_str(TokenKind::LeftParen, "("),
                 Some(')') => self.create_token_str(TokenKind::RightParen, ")"),
-                Some('{') => self.create_token_str(TokenKind::LeftBrace, "{"),
-                Some('}') => self.create_token_str(TokenKind::RightBrace, "}"),
-                Some(',') => self.create_token_str(TokenKind::Comma, ","),
-                Some('.') => self.create_token_str(TokenKind::Dot, "."),
-                Some('-') => self.create_token_str(TokenKind::Minus, "-"),
-                Some('+') => self.create_token_str(TokenKind::Plus, "+"),
-                Some(';') => self.create_token_str(TokenKind::Semicolon, ";"),
-                Some('*') => self.create_token_str(TokenKind::Star, "*"),
-                Some('!') => match self.peek_match('=') {
-                    true => {
-                        self.reader.next();
+                Some('{') => {
+                    if let Some(interpolation) = self.interpolations.last_mut() {
+                        interpolation.depth += 1;
+                    }
 
-                        self.create_token_str(TokenKind::BangEqual, "!=")
+                    self.create_token_str(TokenKind::LeftBrace, "{")
+                }
+                Some('}') => match self.interpolations.last_mut() {
+                    // this brace closes an interpolation - continue scanning the enclosing string
+                    Some(Interpolation { depth: 0, triple_quoted }) => {
+                        let triple_quoted = *triple_quoted;
+                        self.interpolations.pop();
+
+                        return Some(self.create_string_segment_token(triple_quoted, true));
                     }
-                    false => self.create_token_str(TokenKind::Bang, "!"),
+                    Some(interpolation) => {
+                        interpolation.depth -= 1;
+
+                        self.create_token_str(TokenKind::RightBrace, "}")
+                    }
+                    None => self.create_token_str(TokenKind::RightBrace, "}"),
                 },
-                Some('=') => match self.peek_match('=') {
+                Some('[') => self.create_token_str(TokenKind::LeftBracket, "["),
+                Some(']') => self.create_token_str(TokenKind::RightBracket, "]"),
+                Some(',') => self.create_token_str(TokenKind::Comma, ","),
+                Some(':') => self.create_token_str(TokenKind::Colon, ":"),
+                Some('.') => match self.peek_match('.') {
                     true => {
                         self.reader.next();
 
-                        self.create_token_str(TokenKind::EqualEqual, "==")
+                        match self.reader.next_if(|c| *c == '=') {
+                            Some(_) => self.create_token_str(TokenKind::DotDotEqual, "..="),
+                            None => self.create_token_str(TokenKind::DotDot, ".."),
+                        }
                     }
-                    false => self.create_token_str(TokenKind::Equal, "="),
+                    false => self.create_token_str(TokenKind::Dot, "."),
                 },
-                Some('<') => match self.peek_match('=') {
+                Some('-') => self.create_token_str(TokenKind::Minus, "-"),
+                Some('+') => self.create_token_str(TokenKind::Plus, "+"),
+                Some(';') => self.create_token_str(TokenKind::Semicolon, ";"),
+                Some('%') => self.create_token_str(TokenKind::Percent, "%"),
+                Some('*') => match self.peek_match('*') {
                     true => {
                         self.reader.next();
 
-                        self.create_token_str(TokenKind::LessEqual, "<=")
+                        self.create_token_str(TokenKind::StarStar, "**")
                     }
-                    false => self.create_token_str(TokenKind::Less, "<"),
+                    false => self.create_token_str(TokenKind::Star, "*"),
                 },
-                Some('>') => match self.peek_match('=') {
+                // `//` already starts a comment, so integer division is spelled `~/`
+                Some('~') if self.peek_match('/') => {
+                    self.reader.next();
+
+                    self.create_token_str(TokenKind::TildeSlash, "~/")
+                }
+                Some('~') => self.create_token_str(TokenKind::Tilde, "~"),
+                Some('!') => match self.peek_match('=') {
                     true => {
                         self.reader.next();
 
-                        self.create_token_str(TokenKind::GreaterEqual, ">=")
+                        self.create_token_str(TokenKind::BangEqual, "!=")
                     }
-                    false => self.create_token_str(TokenKind::Greater, ">"),
+                    false => self.create_token_str(TokenKind::Bang, "!"),
+                },
+                Some('=') => match self.reader.next_if(|c| *c == '=' || *c == '>') {
+                    Some('=') => self.create_token_str(TokenKind::EqualEqual, "=="),
+                    Some(_) => self.create_token_str(TokenKind::FatArrow, "=>"),
+                    None => self.create_token_str(TokenKind::Equal, "="),
                 },
+                Some('<') => match self.reader.next_if(|c| *c == '=' || *c == '<') {
+                    Some('=') => self.create_token_str(TokenKind::LessEqual, "<="),
+                    Some(_) => self.create_token_str(TokenKind::LessLess, "<<"),
+                    None => self.create_token_str(TokenKind::Less, "<"),
+                },
+                Some('>') => match self.reader.next_if(|c| *c == '=' || *c == '>') {
+                    Some('=') => self.create_token_str(TokenKind::GreaterEqual, ">="),
+                    Some(_) => self.create_token_str(TokenKind::GreaterGreater, ">>"),
+                    None => self.create_token_str(TokenKind::Greater, ">"),
+                },
+                Some('&') => self.create_token_str(TokenKind::Ampersand, "&"),
+                Some('|') => self.create_token_str(TokenKind::Pipe, "|"),
+                Some('^') => self.create_token_str(TokenKind::Caret, "^"),
                 Some('/') => match self.peek_match('/') {
                     // if there's a second slash, this is a comment - pop characters until the next newline
                     true => match self.reader.find(|&next_c| next_c == '\n') {
-                        Some(_) => continue,
+                        Some(_) => {
+                            self.line += 1;
+                            continue;
+                        }
                         None => self.create_token_str(TokenKind::Eof, "eof"),
                     },
+                    false if self.peek_match('*') => {
+                        self.reader.next();
+
+                        if let Err(e) = self.skip_block_comment() {
+                            return Some(Err(e));
+                        }
+
+                        continue;
+                    }
                     false => self.create_token_str(TokenKind::Slash, "/"),
                 },
                 Some(' ') | Some('\r') | Some('\t') => continue,
@@ -204,6 +584,11 @@ impl<'a> Scanner<'a> {
                     continue;
                 }
                 Some('"') => return Some(self.create_string_token()),
+                Some('r') if self.peek_match('"') => {
+                    self.reader.next();
+
+                    return Some(self.create_raw_string_token());
+                }
                 Some(digit_char) if self.is_digit(digit_char) => {
                     return Some(self.create_number_token(digit_char))
                 }
@@ -232,6 +617,12 @@ impl<'a> Iterator for Scanner<'a> {
     type Item = Result<Token, LoxError>;
 
     fn next(&mut self) -> Option<Self::Item> {
-        self.match_next_token()
+        let result = self.match_next_token()?;
+        let column = self.token_column;
+
+        Some(match result {
+            Ok(token) => Ok(Token { column, ..token }),
+            Err(e) => Err(e.or_kind(ErrorKind::Lex).or_column(column)),
+        })
     }
 }
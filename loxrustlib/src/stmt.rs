@@ -4,14 +4,20 @@ use crate::{expr::Expression, token::Token};
 pub enum Statement {
     ExpressionStatement {
         expression: Expression,
+        line: usize,
     },
     PrintStatement {
         printable: Expression,
+        line: usize,
     },
     VariableDeclaration {
         identifier: Token,
         initializer: Option<Expression>,
     },
+    ConstDeclaration {
+        identifier: Token,
+        initializer: Expression,
+    },
     BlockStatement {
         statements: Vec<Statement>,
     },
@@ -19,18 +25,141 @@ pub enum Statement {
         condition: Expression,
         true_branch: Box<Statement>,
         else_branch: Option<Box<Statement>>,
+        line: usize,
     },
     WhileStatement {
         condition: Expression,
         body: Box<Statement>,
+        line: usize,
+    },
+    DoWhileStatement {
+        body: Box<Statement>,
+        condition: Expression,
+        line: usize,
+    },
+    ForInStatement {
+        variable: Token,
+        iterable: Expression,
+        body: Box<Statement>,
     },
     FunDeclaration {
         name: Token,
         parameters: Vec<Token>,
-        body: Vec<Statement>
+        body: Vec<Statement>,
+        is_generator: bool,
     },
     ReturnStatement {
         keyword: Token,
         value: Expression
+    },
+    MatchStatement {
+        keyword: Token,
+        subject: Expression,
+        arms: Vec<MatchArm>,
+    },
+    YieldStatement {
+        keyword: Token,
+        value: Expression,
+    },
+}
+
+impl Statement {
+    /// Whether executing this statement can suspend the generator it's in. Nested function declarations
+    /// are not looked into, since their yields belong to them.
+    pub fn contains_yield(&self) -> bool {
+        match self {
+            Statement::YieldStatement { .. } => true,
+            Statement::BlockStatement { statements } => statements.iter().any(|s| s.contains_yield()),
+            Statement::IfStatement {
+                true_branch,
+                else_branch,
+                ..
+            } => true_branch.contains_yield() || else_branch.as_ref().is_some_and(|e| e.contains_yield()),
+            Statement::WhileStatement { body, .. }
+            | Statement::DoWhileStatement { body, .. }
+            | Statement::ForInStatement { body, .. } => body.contains_yield(),
+            Statement::MatchStatement { arms, .. } => arms.iter().any(|a| a.body.contains_yield()),
+            _ => false,
+        }
+    }
+
+    /// A single line describing the statement, without any of the statements nested in it
+    pub fn summary(&self) -> String {
+        match self {
+            Statement::ExpressionStatement { .. } => "expression".to_string(),
+            Statement::PrintStatement { .. } => "print".to_string(),
+            Statement::VariableDeclaration { identifier, .. } => format!("var {}", identifier.lexeme),
+            Statement::ConstDeclaration { identifier, .. } => format!("const {}", identifier.lexeme),
+            Statement::BlockStatement { statements } => format!("block of {} statements", statements.len()),
+            Statement::IfStatement { .. } => "if".to_string(),
+            Statement::WhileStatement { .. } => "while".to_string(),
+            Statement::DoWhileStatement { .. } => "do while".to_string(),
+            Statement::ForInStatement { variable, .. } => format!("for {} in", variable.lexeme),
+            Statement::FunDeclaration { name, parameters, .. } => format!(
+                "fun {}({})",
+                name.lexeme,
+                parameters.iter().map(|p| p.lexeme.as_str()).collect::<Vec<_>>().join(", ")
+            ),
+            Statement::ReturnStatement { keyword, .. } => format!("return at line {}", keyword.line),
+            Statement::MatchStatement { keyword, .. } => format!("match at line {}", keyword.line),
+            Statement::YieldStatement { keyword, .. } => format!("yield at line {}", keyword.line),
+        }
+    }
+
+    /// The line the statement starts on. Blocks don't have one, each of their statements has its own.
+    pub fn line(&self) -> Option<usize> {
+        match self {
+            Statement::ExpressionStatement { line, .. }
+            | Statement::PrintStatement { line, .. }
+            | Statement::IfStatement { line, .. }
+            | Statement::WhileStatement { line, .. }
+            | Statement::DoWhileStatement { line, .. } => Some(*line),
+            Statement::VariableDeclaration { identifier, .. } | Statement::ConstDeclaration { identifier, .. } => {
+                Some(identifier.line)
+            }
+            Statement::ForInStatement { variable, .. } => Some(variable.line),
+            Statement::FunDeclaration { name, .. } => Some(name.line),
+            Statement::ReturnStatement { keyword, .. }
+            | Statement::MatchStatement { keyword, .. }
+            | Statement::YieldStatement { keyword, .. } => Some(keyword.line),
+            Statement::BlockStatement { .. } => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct MatchArm {
+    pub patterns: Vec<Pattern>,
+    pub guard: Option<Expression>,
+    pub body: Box<Statement>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Pattern {
+    Literal(Expression),
+    Range {
+        start: Expression,
+        end: Expression,
+        inclusive: bool,
+    },
+    /// Matches anything and makes the matched value available to the guard and body under this name
+    Binding(Token),
+    Wildcard,
+    /// `[a, b]` matches a `LoxList` with exactly as many items, each matching its pattern
+    List(Vec<Pattern>),
+    /// `{key: v}` matches a `LoxMap` that has each of the keys, whose values match their patterns. Other
+    /// keys are ignored.
+    Map(Vec<(String, Pattern)>),
+}
+
+impl Pattern {
+    /// The names the pattern binds, including the ones nested in lists and maps
+    pub fn bindings(&self) -> Vec<&Token> {
+        match self {
+            Pattern::Binding(name) => vec![name],
+            Pattern::List(items) => items.iter().flat_map(|p| p.bindings()).collect(),
+            Pattern::Map(entries) => entries.iter().flat_map(|(_, p)| p.bindings()).collect(),
+            Pattern::Literal(_) | Pattern::Range { .. } | Pattern::Wildcard => Vec::new(),
+        }
     }
 }
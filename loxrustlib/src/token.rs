@@ -1,15 +1,24 @@
 use std::fmt::Display;
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone)]
 pub struct Token {
     pub kind: TokenKind,
     pub lexeme: String,
     pub line: usize,
+    /// Where on its line the token starts, 0 if unknown
+    pub column: usize,
 }
 
 impl Token {
     pub fn new(kind: TokenKind, lexeme: String, line: usize) -> Self {
-        Self { line, lexeme, kind }
+        Self { line, lexeme, kind, column: 0 }
+    }
+}
+
+/// The column is only there for error messages, so tokens written out by hand compare equal to scanned ones
+impl PartialEq for Token {
+    fn eq(&self, other: &Self) -> bool {
+        self.kind == other.kind && self.lexeme == other.lexeme && self.line == other.line
     }
 }
 
@@ -25,13 +34,21 @@ pub enum TokenKind {
     RightParen,
     LeftBrace,
     RightBrace,
+    LeftBracket,
+    RightBracket,
     Comma,
+    Colon,
     Dot,
     Minus,
     Plus,
     Semicolon,
     Slash,
     Star,
+    Percent,
+    Ampersand,
+    Pipe,
+    Caret,
+    Tilde,
 
     // One or two character tokens.
     Bang,
@@ -42,20 +59,35 @@ pub enum TokenKind {
     GreaterEqual,
     Less,
     LessEqual,
+    StarStar,
+    TildeSlash,
+    LessLess,
+    GreaterGreater,
+    FatArrow,
+    DotDot,
+    DotDotEqual,
 
     // Literals.
     Identifier(String),
     String(String),
+    InterpolationStart(String),
+    InterpolationMiddle(String),
+    InterpolationEnd(String),
     Number(f64),
+    Integer(i64),
     Boolean(bool),
 
     // Keywords.
     And,
     Class,
+    Const,
+    Do,
     Else,
     Fun,
     For,
     If,
+    In,
+    Match,
     Nil,
     Or,
     Return,
@@ -63,6 +95,7 @@ pub enum TokenKind {
     This,
     Var,
     While,
+    Yield,
 
     // Print
     Print,
@@ -76,6 +109,7 @@ impl Display for TokenKind {
             TokenKind::Identifier(i) => write!(f, "{}", i),
             TokenKind::String(s) => write!(f, "{}", s),
             TokenKind::Number(n) => write!(f, "{}", n),
+            TokenKind::Integer(i) => write!(f, "{}", i),
             TokenKind::Boolean(b) => write!(f, "{}", b),
             _ => write!(f, "{:?}", self),
         }
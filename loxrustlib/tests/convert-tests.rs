@@ -0,0 +1,142 @@
+#![cfg(feature = "serde")]
+
+use std::collections::BTreeMap;
+
+use loxrustlib::{
+    collections::{LoxList, LoxMap},
+    convert::{from_value, to_value},
+    expr::Expression,
+    interpreter::Interpreter,
+    parser::Parser,
+    scan::Scanner,
+};
+use serde::{de::IgnoredAny, ser::SerializeSeq, Deserialize, Serialize, Serializer};
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+enum Level {
+    Warn,
+    Error { code: u32 },
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct Limit {
+    name: String,
+    max: i64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct Config {
+    threshold: f64,
+    label: Option<String>,
+    limits: Vec<Limit>,
+    levels: Vec<Level>,
+    tags: BTreeMap<String, u8>,
+}
+
+/// A sequence that holds itself, like an `Rc` cycle would
+struct Endless;
+
+impl Serialize for Endless {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut seq = serializer.serialize_seq(Some(1))?;
+        seq.serialize_element(self)?;
+        seq.end()
+    }
+}
+
+fn config() -> Config {
+    Config {
+        threshold: 0.5,
+        label: None,
+        limits: vec![
+            Limit { name: "cpu".to_string(), max: 80 },
+            Limit { name: "memory".to_string(), max: 1024 },
+        ],
+        levels: vec![Level::Warn, Level::Error { code: 7 }],
+        tags: BTreeMap::from([("a".to_string(), 1), ("b".to_string(), 2)]),
+    }
+}
+
+#[test]
+pub fn convert_round_trip_test() {
+    let value = to_value(&config()).unwrap();
+
+    assert_eq!(config(), from_value::<Config>(&value).unwrap());
+}
+
+#[test]
+pub fn convert_script_access_test() {
+    let source = r#"
+        fun adjust(config) {
+            var total = 0;
+            for (limit in config.limits) total = total + limit.max;
+
+            config.label = "total ${total}";
+            config.limits.get(0).max = config.limits.get(0).max / 2;
+            return config;
+        }
+    "#;
+
+    let mut interpreter = Interpreter::new();
+    assert!(interpreter.interpret(Parser::new(Scanner::new(source)).parse().unwrap()).is_ok());
+
+    let result = interpreter.call_function("adjust", &[to_value(&config()).unwrap()]).unwrap();
+    let adjusted: Config = from_value(&result).unwrap();
+
+    assert_eq!(Some("total 1104".to_string()), adjusted.label);
+    assert_eq!(40, adjusted.limits[0].max);
+}
+
+#[test]
+pub fn convert_error_path_test() {
+    let value = to_value(&config()).unwrap();
+
+    let Expression::Object(config) = &value else { panic!("Expected a map") };
+    let Some(Expression::Object(limits)) = config.get("limits").unwrap() else { panic!("Expected a list") };
+    let Expression::Object(limit) = limits.call_method("get", &[Expression::LiteralInteger(1)]).unwrap() else { panic!("Expected a map") };
+
+    limit.set("name", Expression::LiteralBoolean(true)).unwrap();
+
+    let err = from_value::<Config>(&value).unwrap_err();
+
+    assert_eq!("limits[1].name", err.path());
+    assert_eq!("limits[1].name: invalid type: boolean `true`, expected a string", err.to_string());
+}
+
+#[test]
+pub fn convert_integers_match_typed_globals_test() {
+    let mut interpreter = Interpreter::new();
+    assert!(interpreter.run_source("var half = 10 / 2; var fraction = 5 / 2;").is_ok());
+
+    for name in ["half", "fraction"] {
+        let value = interpreter.get_global(name).unwrap();
+
+        assert_eq!(from_value::<i64>(&value).ok(), interpreter.get_global_as::<i64>(name).ok());
+    }
+
+    assert_eq!(Some(5), interpreter.get_global_as::<i64>("half").ok());
+    assert!(interpreter.get_global_as::<i64>("fraction").is_err());
+}
+
+#[test]
+pub fn convert_cycles_test() {
+    let list = LoxList::default().into_value();
+    let Expression::Object(inner) = &list else { panic!("Expected a list") };
+    inner.call_method("push", std::slice::from_ref(&list)).unwrap();
+
+    let err = from_value::<IgnoredAny>(&list).unwrap_err();
+
+    assert_eq!("[0]: Cannot convert a List that contains itself", err.to_string());
+
+    let map = LoxMap::default().into_value();
+    let Expression::Object(inner) = &map else { panic!("Expected a map") };
+    inner.set("again", map.clone()).unwrap();
+
+    let err = from_value::<IgnoredAny>(&map).unwrap_err();
+
+    assert_eq!("again: Cannot convert a Map that contains itself", err.to_string());
+
+    let err = to_value(&Endless).unwrap_err();
+
+    assert!(err.message().contains("may contain itself"));
+}
@@ -0,0 +1,644 @@
+use std::time::Duration;
+
+use loxrustlib::{
+    collections::{LoxList, LoxMap},
+    err::{ErrorKind, LoxError, ScriptError, Span}, expr::{Expression, Value}, interpreter::Interpreter,
+    limits::{ExecutionLimits, RECOMMENDED_MAX_CALL_DEPTH}, object::{HostObject, ObjectRef}, outcome::BreakReason, output::CapturedOutput,
+    parser::Parser, scan::Scanner,
+};
+
+#[test]
+pub fn interpreter_bitwise_operations_test() {
+    let interpreter = run("var a = (0b1011 >> 1) & ~0b0010 | 1 << 4 ^ 0b1;");
+
+    assert_eq!(Some(Expression::LiteralInteger(21)), global(&interpreter, "a"));
+}
+
+#[test]
+pub fn interpreter_bitwise_rejects_fractions_test() {
+    let scanner = Scanner::new("var a = 1.5 & 1;");
+    let statements = Parser::new(scanner).parse().unwrap();
+
+    assert!(Interpreter::new().interpret(statements).is_err());
+}
+
+#[test]
+pub fn interpreter_integer_arithmetic_test() {
+    let interpreter = run("var big = 9007199254740993 + 2; var half = 7 / 2; var floored = -7 ~/ 2; var mixed = 1 + 0.5;");
+
+    assert_eq!(Some(Expression::LiteralInteger(9007199254740995)), global(&interpreter, "big"));
+    assert_eq!(Some(Expression::LiteralNumber(3.5)), global(&interpreter, "half"));
+    assert_eq!(Some(Expression::LiteralInteger(-4)), global(&interpreter, "floored"));
+    assert_eq!(Some(Expression::LiteralNumber(1.5)), global(&interpreter, "mixed"));
+}
+
+#[test]
+pub fn interpreter_integer_overflow_test() {
+    let scanner = Scanner::new("var a = 9223372036854775807 + 1;");
+    let statements = Parser::new(scanner).parse().unwrap();
+
+    assert!(Interpreter::new().interpret(statements).is_err());
+
+    let interpreter = run("var m = (-9223372036854775807 - 1) % -1;");
+
+    assert_eq!(Some(Expression::LiteralInteger(0)), global(&interpreter, "m"));
+}
+
+#[test]
+pub fn interpreter_prints_floats_apart_from_integers_test() {
+    let output = CapturedOutput::new();
+    let mut interpreter = Interpreter::new();
+    interpreter.set_output(output.clone());
+
+    assert!(interpreter.run_source("print 3; print 3.0; print 6 / 2; print 2.5; print \"${1.0 + 1}\";").is_ok());
+    assert_eq!("3\n3.0\n3.0\n2.5\n2.0\n", output.printed());
+}
+
+#[test]
+pub fn interpreter_exponent_test() {
+    let interpreter = run("var right = 2 ** 3 ** 2; var negated = -2 ** 2; var fraction = 2 ** -1;");
+
+    assert_eq!(Some(Expression::LiteralInteger(512)), global(&interpreter, "right"));
+    assert_eq!(Some(Expression::LiteralInteger(-4)), global(&interpreter, "negated"));
+    assert_eq!(Some(Expression::LiteralNumber(0.5)), global(&interpreter, "fraction"));
+}
+
+#[test]
+pub fn interpreter_floored_division_test() {
+    let interpreter = run("var a = -7 % 3; var b = 7 % -3; var c = -7 ~/ 2; var d = -7.5 % 2;");
+
+    assert_eq!(Some(Expression::LiteralInteger(2)), global(&interpreter, "a"));
+    assert_eq!(Some(Expression::LiteralInteger(-2)), global(&interpreter, "b"));
+    assert_eq!(Some(Expression::LiteralInteger(-4)), global(&interpreter, "c"));
+    assert_eq!(Some(Expression::LiteralNumber(0.5)), global(&interpreter, "d"));
+}
+
+#[test]
+pub fn interpreter_match_statement_test() {
+    let interpreter = run(r#"
+        var results = "";
+        fun classify(v) {
+            match (v) {
+                "a" | "b" => results = results + "letter,";
+                0..10 => results = results + "small,";
+                n if n >= 100 => results = results + "big ${n},";
+                _ => results = results + "other,";
+            }
+        }
+        classify("b"); classify(3); classify(2.5); classify(10); classify(150);
+    "#);
+
+    assert_eq!(
+        Some(Expression::LiteralString("letter,small,small,other,big 150,".to_string())),
+        global(&interpreter, "results")
+    );
+}
+
+#[test]
+pub fn interpreter_match_destructuring_test() {
+    let output = CapturedOutput::new();
+    let mut interpreter = Interpreter::new();
+    interpreter.set_output(output.clone());
+
+    let list = |items: Vec<Value>| LoxList::new(items).into_value();
+    let map = |entries: &[(&str, Value)]| {
+        LoxMap::new(entries.iter().map(|(k, v)| (k.to_string(), v.clone())).collect()).into_value()
+    };
+
+    interpreter.set_global("values", list(vec![
+        list(vec![]),
+        list(vec![Value::from(1), Value::from(2)]),
+        list(vec![Value::from(3), list(vec![Value::from(4), Value::from(5)])]),
+        map(&[("name", Value::from("ada")), ("age", Value::from(12))]),
+        map(&[("name", Value::from("bob")), ("age", Value::from(40))]),
+        map(&[("age", Value::from(7))]),
+        Value::from("text"),
+    ]));
+
+    let source = r#"
+        for (v in values) {
+            match (v) {
+                [] => print "empty";
+                [1, b] => print "one then ${b}";
+                [a, [b, c]] => print "${a} ${b} ${c}";
+                {name: n, "age": 0..18} => print "minor ${n}";
+                {name: n} => print "named ${n}";
+                _ => print "other";
+            }
+        }
+    "#;
+
+    assert!(interpreter.run_source(source).is_ok());
+    assert_eq!("empty\none then 2\n3 4 5\nminor ada\nnamed bob\nother\nother\n", output.printed());
+
+    let error = interpreter.run_source("match (1) { [a, a] => print a; }").unwrap_err();
+
+    assert!(matches!(error, ScriptError::Parse(_)));
+    assert_eq!(error.lox_error().unwrap().message(), "'a' is bound more than once in the pattern");
+}
+
+#[test]
+pub fn interpreter_for_in_test() {
+    let interpreter = run(r#"
+        var sum = 0;
+        for (i in 1..=4) sum = sum + i;
+
+        var reversed = "";
+        for (c in "abc") reversed = c + reversed;
+
+        var calls = 0;
+        fun countdown() {
+            calls = calls + 1;
+            if (calls > 3) return nil;
+            return calls;
+        }
+        var total = 0;
+        for (n in countdown) total = total + n;
+
+        var runs = 0;
+        do runs = runs + 1; while (false);
+    "#);
+
+    assert_eq!(Some(Expression::LiteralInteger(10)), global(&interpreter, "sum"));
+    assert_eq!(Some(Expression::LiteralString("cba".to_string())), global(&interpreter, "reversed"));
+    assert_eq!(Some(Expression::LiteralInteger(6)), global(&interpreter, "total"));
+    assert_eq!(Some(Expression::LiteralInteger(1)), global(&interpreter, "runs"));
+}
+
+#[test]
+pub fn interpreter_rejects_constant_reassignment_at_runtime_test() {
+    // the function is declared before the constant, so only the runtime check can catch this
+    let scanner = Scanner::new("fun f() { LIMIT = 3; } const LIMIT = 10; f();");
+    let statements = Parser::new(scanner).parse().unwrap();
+
+    let mut interpreter = Interpreter::new();
+
+    assert!(interpreter.interpret(statements).is_err());
+    assert_eq!(Some(Expression::LiteralInteger(10)), global(&interpreter, "LIMIT"));
+}
+
+#[test]
+pub fn interpreter_rejects_redeclaring_constants_at_runtime_test() {
+    for source in ["const X = 1; var X = 2; X = 3;", "const X = 1; const X = 5;"] {
+        // `interpret` skips the resolver, leaving it to the environment
+        let statements = Parser::new(Scanner::new(source)).parse().unwrap();
+        let mut interpreter = Interpreter::new();
+
+        let Err(BreakReason::Errored(e)) = interpreter.interpret(statements) else { panic!("{} ran", source) };
+
+        assert_eq!(e.kind(), ErrorKind::ConstantAssignment);
+        assert_eq!(Some(Expression::LiteralInteger(1)), global(&interpreter, "X"));
+    }
+}
+
+#[test]
+pub fn interpreter_generator_test() {
+    let interpreter = run(r#"
+        var started = false;
+        fun* numbers(n) {
+            started = true;
+            var i = 0;
+            while (i < n) {
+                if (i % 2 == 0) yield i;
+                i = i + 1;
+            }
+            for (c in "ab") yield c;
+            return nil;
+            yield "unreachable";
+        }
+
+        var gen = numbers(5);
+        var lazy = !started;
+        var first = next(gen);
+        var results = "";
+        for (v in gen) results = "${results}${v},";
+        var done = next(gen);
+    "#);
+
+    assert_eq!(Some(Expression::LiteralBoolean(true)), global(&interpreter, "lazy"));
+    assert_eq!(Some(Expression::LiteralInteger(0)), global(&interpreter, "first"));
+    assert_eq!(Some(Expression::LiteralString("2,4,a,b,".to_string())), global(&interpreter, "results"));
+    assert_eq!(Some(Expression::Nil), global(&interpreter, "done"));
+}
+
+#[test]
+pub fn interpreter_captures_output_test() {
+    let scanner = Scanner::new(include_str!("../../loxrust/res/return-test.lox"));
+    let statements = Parser::new(scanner).parse().unwrap();
+
+    let output = CapturedOutput::new();
+    let mut interpreter = Interpreter::new();
+    interpreter.set_output(output.clone());
+
+    assert!(interpreter.interpret(statements).is_ok());
+    assert_eq!("a > 100\na > 5\na <= 5\n", output.printed());
+    assert_eq!("", output.errors());
+}
+
+#[test]
+pub fn interpreter_trace_test() {
+    let scanner = Scanner::new("fun add(a, b) { return a + b; } var x = add(1, 2); print x;");
+    let statements = Parser::new(scanner).parse().unwrap();
+
+    let output = CapturedOutput::new();
+    let mut interpreter = Interpreter::new();
+    interpreter.set_output(output.clone());
+    interpreter.set_trace(true);
+
+    assert!(interpreter.interpret(statements).is_ok());
+    assert_eq!("3\n", output.printed());
+    assert_eq!(
+        "fun add(a, b)\nvar x\ncall add(1, 2)\n  return at line 1\nadd returned 3\ndefine x = 3\nprint\n",
+        output.errors()
+    );
+}
+
+#[test]
+pub fn interpreter_errors_are_quiet_without_trace_test() {
+    let scanner = Scanner::new("var a = 1; a = undefined;");
+    let statements = Parser::new(scanner).parse().unwrap();
+
+    let output = CapturedOutput::new();
+    let mut interpreter = Interpreter::new();
+    interpreter.set_output(output.clone());
+
+    assert!(interpreter.interpret(statements).is_err());
+    assert_eq!("", output.printed());
+    assert_eq!("", output.errors());
+}
+
+#[test]
+pub fn interpreter_step_budget_test() {
+    let scanner = Scanner::new("while (true) {}");
+    let statements = Parser::new(scanner).parse().unwrap();
+
+    let mut interpreter = Interpreter::new();
+    interpreter.set_limits(ExecutionLimits {
+        max_steps: Some(1000),
+        ..ExecutionLimits::default()
+    });
+
+    let Err(BreakReason::Errored(e)) = interpreter.interpret(statements) else { panic!("Expected the budget to run out") };
+
+    assert!(e.to_string().contains("Execution budget exceeded"));
+}
+
+#[test]
+pub fn interpreter_timeout_test() {
+    let scanner = Scanner::new("var i = 0; while (true) { i = i + 1; }");
+    let statements = Parser::new(scanner).parse().unwrap();
+
+    let mut interpreter = Interpreter::new();
+    interpreter.set_limits(ExecutionLimits {
+        timeout: Some(Duration::from_millis(50)),
+        ..ExecutionLimits::default()
+    });
+
+    let Err(BreakReason::Errored(e)) = interpreter.interpret(statements) else { panic!("Expected the script to time out") };
+
+    assert!(e.to_string().contains("Execution timed out"));
+}
+
+#[test]
+pub fn interpreter_stack_overflow_test() {
+    let mut interpreter = Interpreter::new();
+    interpreter.set_limits(ExecutionLimits {
+        max_call_depth: Some(16),
+        ..ExecutionLimits::default()
+    });
+
+    let scanner = Scanner::new("fun f(n) { return f(n + 1); }\nf(0);");
+    let statements = Parser::new(scanner).parse().unwrap();
+
+    let Err(BreakReason::Errored(e)) = interpreter.interpret(statements) else { panic!("the recursion ended") };
+
+    assert_eq!(e.kind(), ErrorKind::StackOverflow);
+    assert!(e.to_string().contains("line 1: Stack overflow"));
+}
+
+#[test]
+pub fn interpreter_stack_overflow_while_iterating_test() {
+    let mut interpreter = Interpreter::new();
+    interpreter.set_limits(ExecutionLimits {
+        max_call_depth: Some(16),
+        ..ExecutionLimits::default()
+    });
+
+    // the generators are created one at a time, so only advancing them nests
+    let source = "fun* wrap(inner) {\n    for (x in inner) yield x;\n}\nvar g = 1..2;\nfor (i in 0..20) g = wrap(g);\nfor (v in g) print v;";
+    let error = interpreter.run_source(source).unwrap_err();
+    let error = error.lox_error().unwrap();
+
+    assert_eq!(error.kind(), ErrorKind::StackOverflow);
+    assert_eq!(error.span().map(|s| s.line), Some(2));
+}
+
+#[test]
+pub fn interpreter_default_limits_stop_deep_recursion_test() {
+    let mut interpreter = Interpreter::new();
+
+    let error = interpreter.run_source("fun f(n) { return f(n + 1); }\nf(0);").unwrap_err();
+    let error = error.lox_error().unwrap();
+
+    assert_eq!(error.kind(), ErrorKind::StackOverflow);
+    assert_eq!(Some(RECOMMENDED_MAX_CALL_DEPTH), ExecutionLimits::default().max_call_depth);
+
+    let interpreter = run("fun d(n) { if (n == 0) return 0; return 1 + d(n - 1); } var depth = d(50);");
+
+    assert_eq!(Some(Expression::LiteralInteger(50)), global(&interpreter, "depth"));
+}
+
+#[test]
+pub fn interpreter_interrupt_test() {
+    let mut interpreter = Interpreter::new();
+    let handle = interpreter.interrupt_handle();
+
+    let interrupter = std::thread::spawn(move || {
+        std::thread::sleep(Duration::from_millis(50));
+        handle.interrupt();
+    });
+
+    let statements = Parser::new(Scanner::new("var i = 0; while (true) { i = i + 1; }")).parse().unwrap();
+
+    assert!(matches!(interpreter.interpret(statements), Err(BreakReason::Interrupted)));
+
+    interrupter.join().unwrap();
+
+    // the interrupt is used up, so the interpreter can carry on with the next script
+    let statements = Parser::new(Scanner::new("i = -1;")).parse().unwrap();
+
+    assert!(interpreter.interpret(statements).is_ok());
+    assert_eq!(Some(Expression::LiteralInteger(-1)), global(&interpreter, "i"));
+}
+
+#[test]
+pub fn interpreter_call_function_test() {
+    let mut interpreter = run(r#"
+        var received = 0;
+        fun on_message(msg) {
+            received = received + 1;
+            return "got ${msg}";
+        }
+    "#);
+
+    let reply = interpreter.call_function("on_message", &[Expression::LiteralString("hello".to_string())]);
+
+    assert_eq!(Some(Expression::LiteralString("got hello".to_string())), reply.ok());
+    assert_eq!(Some(Expression::LiteralInteger(1)), global(&interpreter, "received"));
+
+    let wrong_arity = interpreter.call_function("on_message", &[]).unwrap_err();
+    assert!(wrong_arity.to_string().contains("requires 1 arguments, but was provided 0"));
+
+    let undefined = interpreter.call_function("on_close", &[]).unwrap_err();
+    assert!(undefined.to_string().contains("Call to undefined function 'on_close'"));
+}
+
+#[test]
+pub fn interpreter_call_function_interrupt_test() {
+    let mut interpreter = run("fun spin() { while (true) {} }");
+    interpreter.interrupt_handle().interrupt();
+
+    assert!(matches!(interpreter.call_function("spin", &[]), Err(ScriptError::Interrupted)));
+}
+
+struct Request {
+    path: String,
+    headers: Vec<(String, String)>,
+    status: i64,
+}
+
+impl HostObject for Request {
+    fn type_name(&self) -> &str {
+        "Request"
+    }
+
+    fn get(&self, name: &str) -> Option<Value> {
+        match name {
+            "path" => Some(Expression::LiteralString(self.path.clone())),
+            "status" => Some(Expression::LiteralInteger(self.status)),
+            _ => None,
+        }
+    }
+
+    fn set(&mut self, name: &str, value: Value) -> Result<(), LoxError> {
+        match (name, value) {
+            ("status", Expression::LiteralInteger(status)) => {
+                self.status = status;
+
+                Ok(())
+            }
+            _ => Err(LoxError::with_message(&format!("Cannot set '{}'", name))),
+        }
+    }
+
+    fn call_method(&mut self, name: &str, args: &[Value]) -> Result<Value, LoxError> {
+        match (name, args) {
+            ("header", [Expression::LiteralString(header)]) => Ok(self
+                .headers
+                .iter()
+                .find(|(k, _)| k.eq_ignore_ascii_case(header))
+                .map(|(_, v)| Expression::LiteralString(v.clone()))
+                .unwrap_or(Expression::Nil)),
+            _ => Err(LoxError::with_message(&format!("Undefined method '{}' on Request", name))),
+        }
+    }
+}
+
+#[test]
+pub fn interpreter_self_containing_collections_test() {
+    let output = CapturedOutput::new();
+    let mut interpreter = Interpreter::new();
+    interpreter.set_output(output.clone());
+    interpreter.set_global("list", LoxList::new(vec![Value::from(1)]).into_value());
+    interpreter.set_global("map", LoxMap::default().into_value());
+
+    let source = "list.push(list); print list;\nmap.set(\"self\", map); map.set(\"items\", list); print map;";
+
+    assert!(interpreter.run_source(source).is_ok());
+    assert_eq!("[1, [...]]\n{items: [1, [...]], self: {...}}\n", output.printed());
+}
+
+#[test]
+pub fn interpreter_host_object_test() {
+    let mut interpreter = run(r#"
+        fun handle(req) {
+            req.status = 201;
+            return "${req.path} ${req.header("accept")} ${req.header("missing")}";
+        }
+
+        fun broken(req) {
+            req.path = "/elsewhere";
+        }
+    "#);
+
+    let request = ObjectRef::new(Request {
+        path: "/index".to_string(),
+        headers: vec![("Accept".to_string(), "text/html".to_string())],
+        status: 200,
+    });
+
+    let result = interpreter.call_function("handle", &[Expression::Object(request.clone())]);
+
+    assert_eq!(Some(Expression::LiteralString("/index text/html nil".to_string())), result.ok());
+    assert_eq!(201, request.downcast_ref::<Request>().unwrap().status);
+
+    let err = interpreter.call_function("broken", &[Expression::Object(request)]).unwrap_err();
+
+    assert!(err.to_string().contains("line 8: Cannot set 'path'"));
+}
+
+#[test]
+pub fn interpreter_globals_api_test() {
+    let mut interpreter = Interpreter::new();
+    interpreter.set_global("threshold", 10);
+    interpreter.set_global("name", "sensor");
+
+    let statements = Parser::new(Scanner::new("var over = 12 > threshold; var label = name + \"!\"; var unset;")).parse().unwrap();
+    assert!(interpreter.interpret(statements).is_ok());
+
+    assert_eq!(Some(true), interpreter.get_global_as::<bool>("over").ok());
+    assert_eq!(Some(10.0), interpreter.get_global_as::<f64>("threshold").ok());
+    assert_eq!(Some("sensor!".to_string()), interpreter.get_global_as::<String>("label").ok());
+    assert!(interpreter.get_global_as::<i64>("label").unwrap_err().to_string().contains("Expected an integer, got sensor!"));
+    assert!(interpreter.get_global_as::<i64>("missing").is_err());
+
+    assert_eq!(
+        vec![
+            ("label".to_string(), Expression::from("sensor!")),
+            ("name".to_string(), Expression::from("sensor")),
+            ("over".to_string(), Expression::from(true)),
+            ("threshold".to_string(), Expression::from(10)),
+            ("unset".to_string(), Expression::Nil),
+        ],
+        interpreter.globals()
+    );
+}
+
+#[test]
+pub fn interpreter_run_source_test() {
+    let mut interpreter = Interpreter::new();
+
+    assert!(interpreter.run_source("const LIMIT = 3; fun double(x) { return x * 2; }").is_ok());
+    assert!(matches!(interpreter.run_source("print \"unterminated"), Err(ScriptError::Scan(_))));
+    assert!(matches!(interpreter.run_source("var = 1;"), Err(ScriptError::Parse(_))));
+    assert!(matches!(interpreter.run_source("LIMIT = 4;"), Err(ScriptError::Resolve(_))));
+    assert!(matches!(interpreter.run_source("print missing;"), Err(ScriptError::Runtime(_))));
+
+    assert_eq!(Some(Expression::LiteralInteger(7)), interpreter.eval_expression("double(LIMIT) + 1").ok());
+    assert!(matches!(interpreter.eval_expression("1 + 2; 3"), Err(ScriptError::Parse(_))));
+}
+
+fn run(source: &str) -> Interpreter {
+    let scanner = Scanner::new(source);
+    let statements = Parser::new(scanner).parse().unwrap();
+
+    let mut interpreter = Interpreter::new();
+
+    assert!(interpreter.interpret(statements).is_ok());
+
+    interpreter
+}
+
+fn global(interpreter: &Interpreter, name: &str) -> Option<Expression> {
+    interpreter.get_global(name)
+}
+
+#[test]
+pub fn interpreter_error_kinds_test() {
+    let mut interpreter = Interpreter::new();
+    interpreter.set_output(CapturedOutput::new());
+
+    let kind = |interpreter: &mut Interpreter, source: &str| {
+        interpreter.run_source(source).unwrap_err().lox_error().map(|e| e.kind())
+    };
+
+    assert_eq!(kind(&mut interpreter, "print \"unterminated"), Some(ErrorKind::Unterminated));
+    assert_eq!(kind(&mut interpreter, "print @;"), Some(ErrorKind::Lex));
+    assert_eq!(kind(&mut interpreter, "var = 1;"), Some(ErrorKind::Parse));
+    assert_eq!(kind(&mut interpreter, "const a = 1; a = 2;"), Some(ErrorKind::ConstantAssignment));
+    assert_eq!(kind(&mut interpreter, "print 1 + true;"), Some(ErrorKind::Type));
+    assert_eq!(kind(&mut interpreter, "print 1 / 0;"), Some(ErrorKind::DivisionByZero));
+    assert_eq!(kind(&mut interpreter, "fun f(a) { return a; } f(1, 2);"), Some(ErrorKind::Arity));
+    assert_eq!(kind(&mut interpreter, "missing();"), Some(ErrorKind::UndefinedFunction));
+
+    let error = interpreter.run_source("var b = 1;\nprint b + c;").unwrap_err();
+    let error = error.lox_error().unwrap();
+
+    assert_eq!(error.kind(), ErrorKind::UndefinedVariable);
+    assert_eq!(error.code(), "E0102");
+    assert_eq!(error.span(), Some(Span { line: 2, column: 11 }));
+    assert!(error.to_string().contains("Error E0102 occurred at line 2"));
+}
+
+#[test]
+pub fn interpreter_error_source_test() {
+    use std::error::Error;
+
+    let mut interpreter = Interpreter::new();
+    interpreter.set_global("name", 12);
+
+    let error = interpreter.get_global_as::<String>("name").unwrap_err();
+    let cause = error.source().unwrap().downcast_ref::<LoxError>().unwrap();
+
+    assert_eq!(error.kind(), ErrorKind::Type);
+    assert_eq!(cause.message(), "Expected a string, got 12");
+}
+
+#[test]
+pub fn interpreter_traceback_test() {
+    let mut interpreter = Interpreter::new();
+    interpreter.set_output(CapturedOutput::new());
+
+    let source = "fun inner(x) {\n  return x / 0;\n}\nfun outer(n) {\n  if (n == 0) return inner(n);\n  return outer(n - 1);\n}\nouter(3);";
+    let error = interpreter.run_source(source).unwrap_err();
+    let error = error.lox_error().unwrap();
+
+    let frames: Vec<_> = error.frames().map(|f| (f.function.as_str(), f.line)).collect();
+    assert_eq!(frames, vec![("outer", 8), ("outer", 6), ("outer", 6), ("outer", 6), ("inner", 5)]);
+
+    assert_eq!(
+        error.traceback(),
+        "Traceback (most recent call last):\n  line 8, in <script>\n  line 6, in outer\n  [Previous line repeated 2 more times]\n  line 5, in outer\n  line 2, in inner\n"
+    );
+}
+
+#[test]
+pub fn interpreter_traceback_from_nested_statement_test() {
+    let mut interpreter = Interpreter::new();
+    interpreter.set_output(CapturedOutput::new());
+
+    let source = "fun f(n) {\n  if (n == 0) {\n    print 1 / 0;\n  }\n  return f(n - 1);\n}\nf(2);";
+    let error = interpreter.run_source(source).unwrap_err();
+    let error = error.lox_error().unwrap();
+
+    assert_eq!(error.span().map(|s| s.line), Some(3));
+    assert_eq!(
+        error.traceback(),
+        "Traceback (most recent call last):\n  line 7, in <script>\n  line 5, in f\n  [Previous line repeated 1 more times]\n  line 3, in f\n"
+    );
+
+    let line = |interpreter: &mut Interpreter, source: &str| {
+        interpreter.run_source(source).unwrap_err().lox_error().and_then(|e| e.span()).map(|s| s.line)
+    };
+
+    assert_eq!(line(&mut interpreter, "print 1 / 0;"), Some(1));
+    assert_eq!(line(&mut interpreter, "var i = 0;\nwhile (i < 3)\n  i = i + nil;"), Some(3));
+    assert_eq!(line(&mut interpreter, "fun* g() {\n  while (1 < \"a\") yield 1;\n}\nnext(g());"), Some(2));
+}
+
+#[test]
+pub fn interpreter_reset_test() {
+    let mut interpreter = Interpreter::new();
+    interpreter.set_output(CapturedOutput::new());
+    interpreter.set_global("limit", 3);
+
+    assert!(interpreter.run_source("const a = 1; fun twice(x) { return x * 2; } limit = 4;").is_ok());
+    assert_eq!(interpreter.functions(), vec!["clock", "next", "twice"]);
+
+    interpreter.reset();
+
+    assert_eq!(interpreter.functions(), vec!["clock", "next"]);
+    assert_eq!(interpreter.globals().len(), 1);
+    assert_eq!(Some(3), interpreter.get_global_as::<i64>("limit").ok());
+    assert!(interpreter.run_source("var a = 2;").is_ok());
+}
@@ -1,14 +1,24 @@
-use loxrustlib::{expr::BinaryOperator, expr::Expression, parser::Parser, scan::Scanner};
+use loxrustlib::{
+    err::{ErrorKind, Span},
+    expr::{BinaryOperator, Expression, UnaryOperator},
+    parser::Parser,
+    scan::Scanner,
+    stmt::{MatchArm, Pattern, Statement},
+    token::{Token, TokenKind},
+};
 
 #[test]
 pub fn parser_equality_test() {
-    let expected_tree = Box::new(Expression::Binary {
-        left: Box::new(Expression::LiteralNumber(5.0)),
-        operator: BinaryOperator::Equal,
-        right: Box::new(Expression::LiteralNumber(5.0)),
-    });
+    let expected_tree = vec![Statement::ExpressionStatement {
+        expression: Expression::Binary {
+            left: Box::new(Expression::LiteralInteger(5)),
+            operator: BinaryOperator::Equal,
+            right: Box::new(Expression::LiteralInteger(5)),
+        },
+        line: 1,
+    }];
 
-    let input = "5 == 5".to_string();
+    let input = "5 == 5;".to_string();
 
     let scanner = Scanner::new(&input);
     let mut parser = Parser::new(scanner);
@@ -21,3 +31,234 @@ pub fn parser_equality_test() {
         format!("{:?}", output.unwrap())
     );
 }
+
+#[test]
+pub fn parser_interpolation_test() {
+    let expected_tree = vec![Statement::PrintStatement {
+        printable: Expression::Interpolation {
+            parts: vec![
+                Expression::LiteralString("sum: ".to_string()),
+                Expression::Binary {
+                    left: Box::new(Expression::LiteralInteger(1)),
+                    operator: BinaryOperator::Plus,
+                    right: Box::new(Expression::LiteralInteger(2)),
+                },
+            ],
+        },
+        line: 1,
+    }];
+
+    let input = "print \"sum: ${1 + 2}\";".to_string();
+
+    let scanner = Scanner::new(&input);
+    let mut parser = Parser::new(scanner);
+
+    assert_eq!(expected_tree, parser.parse().unwrap());
+}
+
+#[test]
+pub fn parser_power_precedence_test() {
+    let number = |i: i64| Box::new(Expression::LiteralInteger(i));
+
+    // -2 ** 3 ** 2 % 5 parses as (-(2 ** (3 ** 2))) % 5
+    let expected_tree = vec![Statement::ExpressionStatement {
+        expression: Expression::Binary {
+            left: Box::new(Expression::Unary {
+                operator: UnaryOperator::Minus,
+                right: Box::new(Expression::Binary {
+                    left: number(2),
+                    operator: BinaryOperator::Power,
+                    right: Box::new(Expression::Binary {
+                        left: number(3),
+                        operator: BinaryOperator::Power,
+                        right: number(2),
+                    }),
+                }),
+            }),
+            operator: BinaryOperator::Modulo,
+            right: number(5),
+        },
+        line: 1,
+    }];
+
+    let input = "-2 ** 3 ** 2 % 5;".to_string();
+
+    let scanner = Scanner::new(&input);
+    let mut parser = Parser::new(scanner);
+
+    assert_eq!(expected_tree, parser.parse().unwrap());
+}
+
+#[test]
+pub fn parser_match_statement_test() {
+    let print = |s: &str| {
+        Box::new(Statement::PrintStatement {
+            printable: Expression::LiteralString(s.to_string()),
+            line: 1,
+        })
+    };
+
+    let input = "match (x) { \"a\" | \"b\" => print \"ab\"; 1..=9 if x != 5 => print \"digit\"; _ => print \"other\"; }".to_string();
+
+    let scanner = Scanner::new(&input);
+    let mut parser = Parser::new(scanner);
+
+    let Statement::MatchStatement { arms, .. } = parser.parse().unwrap().remove(0) else { panic!("Expected a match statement") };
+
+    assert_eq!(
+        vec![
+            MatchArm {
+                patterns: vec![
+                    Pattern::Literal(Expression::LiteralString("a".to_string())),
+                    Pattern::Literal(Expression::LiteralString("b".to_string())),
+                ],
+                guard: None,
+                body: print("ab"),
+            },
+            MatchArm {
+                patterns: vec![Pattern::Range {
+                    start: Expression::LiteralInteger(1),
+                    end: Expression::LiteralInteger(9),
+                    inclusive: true,
+                }],
+                guard: Some(Expression::Binary {
+                    left: Box::new(Expression::Identifier(Token::new(TokenKind::Identifier("x".to_string()), "x".to_string(), 1))),
+                    operator: BinaryOperator::NotEqual,
+                    right: Box::new(Expression::LiteralInteger(5)),
+                }),
+                body: print("digit"),
+            },
+            MatchArm {
+                patterns: vec![Pattern::Wildcard],
+                guard: None,
+                body: print("other"),
+            },
+        ],
+        arms
+    );
+}
+
+#[test]
+pub fn parser_match_rejects_arms_after_wildcard_test() {
+    let input = "match (x) {\n _ => print 1;\n 2 => print 2;\n}".to_string();
+
+    let scanner = Scanner::new(&input);
+    let mut parser = Parser::new(scanner);
+
+    let err = parser.parse().unwrap_err().to_string();
+
+    assert!(err.contains("line 3"));
+    assert!(err.contains("Unreachable match arm"));
+}
+
+#[test]
+pub fn parser_for_in_and_do_while_test() {
+    let expected_tree = vec![
+        Statement::ForInStatement {
+            variable: Token::new(TokenKind::Identifier("i".to_string()), "i".to_string(), 1),
+            iterable: Expression::Range {
+                start: Box::new(Expression::LiteralInteger(0)),
+                end: Box::new(Expression::LiteralInteger(3)),
+                inclusive: false,
+            },
+            body: Box::new(Statement::PrintStatement {
+                printable: Expression::Identifier(Token::new(TokenKind::Identifier("i".to_string()), "i".to_string(), 1)),
+                line: 1,
+            }),
+        },
+        Statement::DoWhileStatement {
+            body: Box::new(Statement::BlockStatement { statements: vec![] }),
+            condition: Expression::LiteralBoolean(false),
+            line: 1,
+        },
+    ];
+
+    let input = "for (i in 0..3) print i; do {} while (false);".to_string();
+
+    let scanner = Scanner::new(&input);
+    let mut parser = Parser::new(scanner);
+
+    assert_eq!(expected_tree, parser.parse().unwrap());
+}
+
+#[test]
+pub fn parser_generator_declaration_test() {
+    let input = "fun* gen() { yield 1; }".to_string();
+
+    let scanner = Scanner::new(&input);
+    let mut parser = Parser::new(scanner);
+
+    let Statement::FunDeclaration { is_generator, body, .. } = parser.parse().unwrap().remove(0) else { panic!("Expected a function declaration") };
+
+    assert!(is_generator);
+    assert_eq!(
+        vec![Statement::YieldStatement {
+            keyword: Token::new(TokenKind::Yield, "yield".to_string(), 1),
+            value: Expression::LiteralInteger(1),
+        }],
+        body
+    );
+}
+
+#[test]
+pub fn parser_rejects_yield_outside_generator_test() {
+    let input = "fun f() { yield 1; }".to_string();
+
+    let scanner = Scanner::new(&input);
+    let mut parser = Parser::new(scanner);
+
+    let err = parser.parse().unwrap_err().to_string();
+
+    assert!(err.contains("Cannot yield outside of a generator function"));
+}
+
+#[test]
+pub fn parser_property_access_test() {
+    let identifier = |name: &str| Token::new(TokenKind::Identifier(name.to_string()), name.to_string(), 1);
+
+    let expected_tree = vec![Statement::ExpressionStatement {
+        expression: Expression::Set {
+            object: Box::new(Expression::Identifier(identifier("req"))),
+            name: identifier("status"),
+            value: Box::new(Expression::Call {
+                callee: Box::new(Expression::Get {
+                    object: Box::new(Expression::Identifier(identifier("req"))),
+                    name: identifier("header"),
+                }),
+                closing_parenthesis: Token::new(TokenKind::RightParen, ")".to_string(), 1),
+                arguments: vec![Expression::LiteralInteger(1)],
+            }),
+        },
+        line: 1,
+    }];
+
+    let input = "req.status = req.header(1);".to_string();
+
+    let scanner = Scanner::new(&input);
+    let mut parser = Parser::new(scanner);
+
+    assert_eq!(expected_tree, parser.parse().unwrap());
+}
+
+#[test]
+pub fn parser_errors_point_at_the_offending_token_test() {
+    let error = |input: &str| Parser::new(Scanner::new(input)).parse().unwrap_err();
+
+    let err = error("print 1 +;");
+    assert_eq!(err.kind(), ErrorKind::Parse);
+    assert_eq!(err.span(), Some(Span { line: 1, column: 10 }));
+    assert_eq!(err.message(), "Expected expression, got ';'");
+
+    let err = error("var a = 1;\n  var = 2;");
+    assert_eq!(err.span(), Some(Span { line: 2, column: 7 }));
+
+    let err = error("print (1, 2;");
+    assert_eq!(err.span(), Some(Span { line: 1, column: 12 }));
+
+    let err = error("1 = 2;");
+    assert_eq!(err.span(), Some(Span { line: 1, column: 3 }));
+    assert_eq!(err.message(), "Invalid assignment target");
+
+    let err = error("var x = 1");
+    assert_eq!(err.message(), "Expected token of kind 'Semicolon', instead got the end of the input");
+}
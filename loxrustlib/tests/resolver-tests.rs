@@ -0,0 +1,38 @@
+use loxrustlib::{err::ErrorKind, parser::Parser, resolver::Resolver, scan::Scanner};
+
+#[test]
+pub fn resolver_rejects_constant_reassignment_test() {
+    let err = resolve("const LIMIT = 10;\nfun f() {\n    LIMIT = 3;\n}").unwrap_err();
+
+    // the same kind the environment reports when it catches this at runtime
+    assert_eq!(err.kind(), ErrorKind::ConstantAssignment);
+
+    assert!(err.to_string().contains("line 3"));
+    assert!(err.to_string().contains("Cannot assign to constant 'LIMIT'"));
+}
+
+#[test]
+pub fn resolver_allows_assigning_shadowing_variables_test() {
+    assert!(resolve("const LIMIT = 10; { var LIMIT = 1; LIMIT = 2; } fun f(LIMIT) { LIMIT = 3; }").is_ok());
+}
+
+#[test]
+pub fn resolver_rejects_redeclaring_constants_test() {
+    let err = resolve("const LIMIT = 10;\nvar LIMIT = 2;\nLIMIT = 3;").unwrap_err();
+
+    assert_eq!(err.kind(), ErrorKind::ConstantAssignment);
+    assert!(err.to_string().contains("line 2"));
+    assert!(err.to_string().contains("Cannot redeclare constant 'LIMIT'"));
+
+    let err = resolve("fun f() {\n    const LIMIT = 1;\n    const LIMIT = 5;\n}").unwrap_err();
+
+    assert_eq!(err.kind(), ErrorKind::ConstantAssignment);
+    assert!(err.to_string().contains("line 3"));
+}
+
+fn resolve(source: &str) -> Result<(), loxrustlib::err::LoxError> {
+    let scanner = Scanner::new(source);
+    let statements = Parser::new(scanner).parse().unwrap();
+
+    Resolver::new().resolve(&statements)
+}
@@ -1,5 +1,6 @@
 use loxrustlib::{
-    scan::Scanner,
+    err::{ErrorKind, Span},
+    scan::{keywords, Scanner},
     token::{Token, TokenKind},
 };
 
@@ -7,43 +8,43 @@ use loxrustlib::{
 pub fn test_scanner_every_token() {
     let input = "( ) { } , . - + ; / * ! != = == > >= < <= asdf \"asdf\" 76.12 true and class else fun for if nil or print return super this var while".to_string();
     let expected_output = &[
-        token_of(TokenKind::LeftParen),
-        token_of(TokenKind::RightParen),
-        token_of(TokenKind::LeftBrace),
-        token_of(TokenKind::RightBrace),
-        token_of(TokenKind::Comma),
-        token_of(TokenKind::Dot),
-        token_of(TokenKind::Minus),
-        token_of(TokenKind::Plus),
-        token_of(TokenKind::Semicolon),
-        token_of(TokenKind::Slash),
-        token_of(TokenKind::Star),
-        token_of(TokenKind::Bang),
-        token_of(TokenKind::BangEqual),
-        token_of(TokenKind::Equal),
-        token_of(TokenKind::EqualEqual),
-        token_of(TokenKind::Greater),
-        token_of(TokenKind::GreaterEqual),
-        token_of(TokenKind::Less),
-        token_of(TokenKind::LessEqual),
-        token_of(TokenKind::Identifier("asdf".to_string())),
-        token_of(TokenKind::String("asdf".to_string())),
-        token_of(TokenKind::Number(76.12)),
-        token_of(TokenKind::Boolean(true)),
-        token_of(TokenKind::And),
-        token_of(TokenKind::Class),
-        token_of(TokenKind::Else),
-        token_of(TokenKind::Fun),
-        token_of(TokenKind::For),
-        token_of(TokenKind::If),
-        token_of(TokenKind::Nil),
-        token_of(TokenKind::Or),
-        token_of(TokenKind::Print),
-        token_of(TokenKind::Return),
-        token_of(TokenKind::Super),
-        token_of(TokenKind::This),
-        token_of(TokenKind::Var),
-        token_of(TokenKind::While),
+        token_of(TokenKind::LeftParen, "("),
+        token_of(TokenKind::RightParen, ")"),
+        token_of(TokenKind::LeftBrace, "{"),
+        token_of(TokenKind::RightBrace, "}"),
+        token_of(TokenKind::Comma, ","),
+        token_of(TokenKind::Dot, "."),
+        token_of(TokenKind::Minus, "-"),
+        token_of(TokenKind::Plus, "+"),
+        token_of(TokenKind::Semicolon, ";"),
+        token_of(TokenKind::Slash, "/"),
+        token_of(TokenKind::Star, "*"),
+        token_of(TokenKind::Bang, "!"),
+        token_of(TokenKind::BangEqual, "!="),
+        token_of(TokenKind::Equal, "="),
+        token_of(TokenKind::EqualEqual, "=="),
+        token_of(TokenKind::Greater, ">"),
+        token_of(TokenKind::GreaterEqual, ">="),
+        token_of(TokenKind::Less, "<"),
+        token_of(TokenKind::LessEqual, "<="),
+        token_of(TokenKind::Identifier("asdf".to_string()), "asdf"),
+        token_of(TokenKind::String("asdf".to_string()), "asdf"),
+        token_of(TokenKind::Number(76.12), "76.12"),
+        token_of(TokenKind::Boolean(true), "true"),
+        token_of(TokenKind::And, "and"),
+        token_of(TokenKind::Class, "class"),
+        token_of(TokenKind::Else, "else"),
+        token_of(TokenKind::Fun, "fun"),
+        token_of(TokenKind::For, "for"),
+        token_of(TokenKind::If, "if"),
+        token_of(TokenKind::Nil, "nil"),
+        token_of(TokenKind::Or, "or"),
+        token_of(TokenKind::Print, "print"),
+        token_of(TokenKind::Return, "return"),
+        token_of(TokenKind::Super, "super"),
+        token_of(TokenKind::This, "this"),
+        token_of(TokenKind::Var, "var"),
+        token_of(TokenKind::While, "while"),
     ];
 
     assert_token_stream_equality(expected_output, input);
@@ -52,7 +53,7 @@ pub fn test_scanner_every_token() {
 #[test]
 pub fn test_scanner_matches_double_equals() {
     let input = "==".to_string();
-    let expected_output = &[token_of(TokenKind::EqualEqual)];
+    let expected_output = &[token_of(TokenKind::EqualEqual, "==")];
 
     assert_token_stream_equality(expected_output, input);
 }
@@ -61,9 +62,9 @@ pub fn test_scanner_matches_double_equals() {
 pub fn test_scanner_matches_comparison_equal_equal() {
     let input = "5 == 6".to_string();
     let expected_output = &[
-        token_of(TokenKind::Number(5.0)),
-        token_of(TokenKind::EqualEqual),
-        token_of(TokenKind::Number(6.0)),
+        token_of(TokenKind::Integer(5), "5"),
+        token_of(TokenKind::EqualEqual, "=="),
+        token_of(TokenKind::Integer(6), "6"),
     ];
 
     assert_token_stream_equality(expected_output, input);
@@ -73,14 +74,190 @@ pub fn test_scanner_matches_comparison_equal_equal() {
 pub fn test_scanner_matches_comparison_not_equal() {
     let input = "5 != 6".to_string();
     let expected_output = &[
-        token_of(TokenKind::Number(5.0)),
-        token_of(TokenKind::BangEqual),
-        token_of(TokenKind::Number(6.0)),
+        token_of(TokenKind::Integer(5), "5"),
+        token_of(TokenKind::BangEqual, "!="),
+        token_of(TokenKind::Integer(6), "6"),
     ];
 
     assert_token_stream_equality(expected_output, input);
 }
 
+#[test]
+pub fn test_scanner_string_escape_sequences() {
+    let input = r#""a\n\t\r\\\0\"\x41\u{1F600}""#.to_string();
+    let expected = "a\n\t\r\\\0\"A\u{1F600}";
+    let expected_output = &[token_of(TokenKind::String(expected.to_string()), expected)];
+
+    assert_token_stream_equality(expected_output, input);
+}
+
+#[test]
+pub fn test_scanner_invalid_escape_reports_line() {
+    let input = "\n\n\"bad \\q escape\"".to_string();
+    let mut scanner = Scanner::new(&input);
+
+    let err = scanner.next().unwrap().unwrap_err();
+
+    assert!(err.to_string().contains("line 3"));
+    assert!(err.to_string().contains("Invalid escape sequence '\\q'"));
+}
+
+#[test]
+pub fn test_scanner_raw_and_triple_quoted_strings() {
+    let input = "r\"C:\\dir\\n\" \"\"\"first \"quoted\"\nsecond\"\"\"".to_string();
+    let expected_output = &[
+        token_of(TokenKind::String("C:\\dir\\n".to_string()), "C:\\dir\\n"),
+        token_of_at(
+            TokenKind::String("first \"quoted\"\nsecond".to_string()),
+            "first \"quoted\"\nsecond",
+            2,
+        ),
+    ];
+
+    assert_token_stream_equality(expected_output, input);
+}
+
+#[test]
+pub fn test_scanner_string_interpolation_segments() {
+    let input = "\"a ${b} c ${ {} } d\"".to_string();
+    let expected_output = &[
+        token_of(TokenKind::InterpolationStart("a ".to_string()), "a "),
+        token_of(TokenKind::Identifier("b".to_string()), "b"),
+        token_of(TokenKind::InterpolationMiddle(" c ".to_string()), " c "),
+        token_of(TokenKind::LeftBrace, "{"),
+        token_of(TokenKind::RightBrace, "}"),
+        token_of(TokenKind::InterpolationEnd(" d".to_string()), " d"),
+        token_of(TokenKind::Eof, "eof"),
+    ];
+
+    assert_token_stream_equality(expected_output, input);
+}
+
+#[test]
+pub fn test_scanner_extended_number_literals() {
+    let input = "0xFF 0b1010 0o17 1_000 1e-9 2.5E3 1..2".to_string();
+    let expected_output = &[
+        token_of(TokenKind::Integer(255), "0xFF"),
+        token_of(TokenKind::Integer(10), "0b1010"),
+        token_of(TokenKind::Integer(15), "0o17"),
+        token_of(TokenKind::Integer(1000), "1_000"),
+        token_of(TokenKind::Number(1e-9), "1e-9"),
+        token_of(TokenKind::Number(2500.0), "2.5E3"),
+        token_of(TokenKind::Integer(1), "1"),
+        token_of(TokenKind::DotDot, ".."),
+        token_of(TokenKind::Integer(2), "2"),
+    ];
+
+    assert_token_stream_equality(expected_output, input);
+}
+
+#[test]
+pub fn test_scanner_arithmetic_operators() {
+    let input = "% ** ~/ // comment".to_string();
+    let expected_output = &[
+        token_of(TokenKind::Percent, "%"),
+        token_of(TokenKind::StarStar, "**"),
+        token_of(TokenKind::TildeSlash, "~/"),
+        token_of(TokenKind::Eof, "eof"),
+    ];
+
+    assert_token_stream_equality(expected_output, input);
+}
+
+#[test]
+pub fn test_scanner_bitwise_operators() {
+    let input = "& | ^ ~ << >> <= >= ~/ => ..=".to_string();
+    let expected_output = &[
+        token_of(TokenKind::Ampersand, "&"),
+        token_of(TokenKind::Pipe, "|"),
+        token_of(TokenKind::Caret, "^"),
+        token_of(TokenKind::Tilde, "~"),
+        token_of(TokenKind::LessLess, "<<"),
+        token_of(TokenKind::GreaterGreater, ">>"),
+        token_of(TokenKind::LessEqual, "<="),
+        token_of(TokenKind::GreaterEqual, ">="),
+        token_of(TokenKind::TildeSlash, "~/"),
+        token_of(TokenKind::FatArrow, "=>"),
+        token_of(TokenKind::DotDotEqual, "..="),
+    ];
+
+    assert_token_stream_equality(expected_output, input);
+}
+
+#[test]
+pub fn test_scanner_nested_block_comments() {
+    let input = "1 /* outer\n /* inner */\n still comment */ 2 // line\n3".to_string();
+    let expected_output = &[
+        token_of(TokenKind::Integer(1), "1"),
+        token_of_at(TokenKind::Integer(2), "2", 3),
+        token_of_at(TokenKind::Integer(3), "3", 4),
+    ];
+
+    assert_token_stream_equality(expected_output, input);
+}
+
+#[test]
+pub fn test_scanner_unterminated_block_comment() {
+    let input = "\n/* /* */\n\n".to_string();
+    let mut scanner = Scanner::new(&input);
+
+    let err = scanner.next().unwrap().unwrap_err();
+
+    assert!(err.to_string().contains("line 2"));
+    assert!(err.to_string().contains("Unterminated block comment"));
+}
+
+#[test]
+pub fn test_scanner_keywords_list() {
+    for keyword in keywords() {
+        let token = Scanner::new(keyword).next().unwrap().unwrap();
+
+        assert!(!matches!(token.kind, TokenKind::Identifier(_)), "{} scans as an identifier", keyword);
+    }
+
+    for reserved in ["class", "super", "this"] {
+        let token = Scanner::new(reserved).next().unwrap().unwrap();
+
+        assert!(!matches!(token.kind, TokenKind::Identifier(_)), "{} scans as an identifier", reserved);
+        assert!(!keywords().any(|k| k == reserved), "{} is offered as a keyword", reserved);
+    }
+
+    assert!(matches!(Scanner::new("classy").next().unwrap().unwrap().kind, TokenKind::Identifier(_)));
+}
+
+#[test]
+pub fn test_scanner_columns() {
+    let input = "var answer = 42;\n  print answer;".to_string();
+    let columns: Vec<_> = Scanner::new(&input)
+        .map(|t| t.unwrap())
+        .take_while(|t| t.kind != TokenKind::Eof)
+        .map(|t| (t.line, t.column))
+        .collect();
+
+    assert_eq!(columns, vec![(1, 1), (1, 5), (1, 12), (1, 14), (1, 16), (2, 3), (2, 9), (2, 15)]);
+}
+
+#[test]
+pub fn test_scanner_error_kind() {
+    let input = "var s = \"oops;".to_string();
+    let err = Scanner::new(&input).find_map(|t| t.err()).unwrap();
+
+    assert_eq!(err.kind(), ErrorKind::Unterminated);
+    assert_eq!(err.code(), "E0004");
+    assert_eq!(err.span(), Some(Span { line: 1, column: 9 }));
+
+    let input = "/* never closed\n".to_string();
+    let err = Scanner::new(&input).find_map(|t| t.err()).unwrap();
+
+    assert_eq!(err.kind(), ErrorKind::Unterminated);
+
+    let input = "var s = @;".to_string();
+    let err = Scanner::new(&input).find_map(|t| t.err()).unwrap();
+
+    assert_eq!(err.kind(), ErrorKind::Lex);
+    assert_eq!(err.code(), "E0001");
+}
+
 fn assert_token_stream_equality(expected: &[Token], input: String) {
     let mut scanner = Scanner::new(&input);
 
@@ -100,14 +277,10 @@ fn assert_token_stream_equality(expected: &[Token], input: String) {
     }
 }
 
-fn token_of(kind: TokenKind) -> Token {
-    token_of_at(kind, 1)
+fn token_of(kind: TokenKind, lexeme: &str) -> Token {
+    token_of_at(kind, lexeme, 1)
 }
 
 fn token_of_at(kind: TokenKind, lexeme: &str, line: usize) -> Token {
-    Token {
-        kind,
-        lexeme: lexeme.to_string(),
-        line,
-    }
+    Token::new(kind, lexeme.to_string(), line)
 }